@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::dependent::{Dependent, convert_stars_to_number};
+use crate::error::AppError;
+use crate::github::{Crawl, Crawler};
+use crate::snapshot::save_crawl;
+use crate::trend::record_counts;
+
+/// The result of running the pipeline for one target of a batch.
+pub struct TargetReport {
+    /// The target as `owner/repo`
+    pub target: String,
+    pub config: Config,
+    pub crawl: Crawl,
+    pub top_dependents: Vec<Dependent>,
+    pub elapsed_secs: f64,
+}
+
+/// A dependent that uses several of the batch targets.
+#[derive(Debug, Clone, Serialize)]
+pub struct LeaderboardEntry {
+    pub repo: String,
+    pub stars: String,
+    pub targets: Vec<String>,
+}
+
+/// Per-target rollup of a batch.
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetSummary {
+    pub target: String,
+    /// Dependents count from the page header
+    pub dependents: usize,
+    /// Dependents collected above the star threshold
+    pub collected: usize,
+    /// Collected dependents not owned by the excluded owner
+    pub external: usize,
+    /// External dependents that also use another target
+    pub shared: usize,
+}
+
+pub struct BatchReport {
+    pub reports: Vec<TargetReport>,
+    pub summary: Vec<TargetSummary>,
+    pub leaderboard: Vec<LeaderboardEntry>,
+    /// Targets that could not be crawled, with the reason
+    pub errors: Vec<(String, AppError)>,
+}
+
+impl BatchReport {
+    /// The failure with the highest exit status, which a batch where every
+    /// target failed exits with.
+    pub fn most_severe_error(self) -> Option<AppError> {
+        self.errors.into_iter()
+            .map(|(_, error)| error)
+            .max_by_key(AppError::exit_code)
+    }
+}
+
+/// Expands the positional batch arguments into repository specs. Arguments
+/// naming an existing file are read as one repository per line; blank lines
+/// and lines starting with `#` are skipped. Duplicates are dropped.
+pub fn read_targets(args: &[String]) -> Result<Vec<String>, AppError> {
+    let mut targets: Vec<String> = Vec::new();
+    let mut add = |target: &str| {
+        if !targets.iter().any(|t| t == target) {
+            targets.push(target.to_string());
+        }
+    };
+
+    for arg in args {
+        let path = Path::new(arg);
+        if path.is_file() {
+            let content = fs::read_to_string(path)?;
+            content.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .for_each(&mut add);
+        } else {
+            add(arg);
+        }
+    }
+
+    if targets.is_empty() {
+        return Err(AppError::Other("No repositories to process".to_string()));
+    }
+    Ok(targets)
+}
+
+/// Ranks dependents by how many targets they use, then by stars. Only
+/// dependents of at least `min_targets` targets are kept.
+pub fn build_leaderboard<'a, I>(crawls: I, min_targets: usize) -> Vec<LeaderboardEntry>
+where
+    I: IntoIterator<Item = (&'a str, &'a [Dependent])>,
+{
+    let mut entries: HashMap<&str, LeaderboardEntry> = HashMap::new();
+    for (target, dependents) in crawls {
+        for dep in dependents {
+            let entry = entries.entry(&dep.repo).or_insert_with(|| LeaderboardEntry {
+                repo: dep.repo.clone(),
+                stars: dep.stars.clone(),
+                targets: Vec::new(),
+            });
+            if convert_stars_to_number(&dep.stars) > convert_stars_to_number(&entry.stars) {
+                entry.stars = dep.stars.clone();
+            }
+            if !entry.targets.iter().any(|t| t == target) {
+                entry.targets.push(target.to_string());
+            }
+        }
+    }
+
+    let mut leaderboard: Vec<LeaderboardEntry> = entries.into_values()
+        .filter(|entry| entry.targets.len() >= min_targets)
+        .collect();
+    leaderboard.sort_by(|a, b| {
+        b.targets.len().cmp(&a.targets.len())
+            .then_with(|| convert_stars_to_number(&b.stars)
+                .partial_cmp(&convert_stars_to_number(&a.stars))
+                .unwrap())
+            .then_with(|| a.repo.cmp(&b.repo))
+    });
+    leaderboard
+}
+
+/// Runs the pipeline for every target in turn, sharing one crawler so all
+/// targets go through the same HTTP client, cache and rate limiter. A failing
+/// target is recorded in the report and does not stop the batch. Dependents
+/// owned by `exclude_owner`, e.g. the scanned organization itself, are left
+/// out of the rollups.
+pub async fn run_batch(
+    crawler: &Crawler,
+    config: &Config,
+    targets: &[String],
+    min_targets: usize,
+    exclude_owner: Option<&str>,
+) -> Result<BatchReport, AppError> {
+    let mut reports = Vec::new();
+    let mut errors = Vec::new();
+
+    for target in targets {
+        let target_config = match config.with_target(target) {
+            Ok(target_config) => target_config,
+            Err(e) => {
+                errors.push((target.clone(), e));
+                continue;
+            }
+        };
+        let name = format!("{}/{}", target_config.owner, target_config.repo);
+
+        if !config.quiet {
+            eprintln!("Fetching {} dependents for {}...",
+                if config.is_package { "package" } else { "repository" },
+                name);
+        }
+
+        let start_time = Instant::now();
+        match crawler.crawl(&target_config).await {
+            Ok(crawl) => {
+                record_counts(&target_config, crawl.header);
+                if target_config.save_snapshot {
+                    if let Err(e) = save_crawl(&target_config, &crawl) {
+                        eprintln!("Warning: Could not save snapshot for {}: {}", name, e);
+                    }
+                }
+                let top_dependents = crawler.top_dependents(&target_config, &crawl).await;
+                reports.push(TargetReport {
+                    target: name,
+                    config: target_config,
+                    crawl,
+                    top_dependents,
+                    elapsed_secs: start_time.elapsed().as_secs_f64(),
+                });
+            },
+            Err(e) => errors.push((name, e)),
+        }
+    }
+
+    let is_external = |dep: &Dependent| match exclude_owner {
+        Some(owner) => !dep.repo.split('/').next().unwrap_or("").eq_ignore_ascii_case(owner),
+        None => true,
+    };
+    let external: Vec<(&str, Vec<Dependent>)> = reports.iter()
+        .map(|r| (r.target.as_str(), r.crawl.dependents.iter().filter(|dep| is_external(dep)).cloned().collect()))
+        .collect();
+    let rollup = build_leaderboard(external.iter().map(|(target, deps)| (*target, deps.as_slice())), 1);
+
+    let mut shared: HashMap<&str, usize> = HashMap::new();
+    for entry in rollup.iter().filter(|entry| entry.targets.len() > 1) {
+        for target in &entry.targets {
+            *shared.entry(target.as_str()).or_default() += 1;
+        }
+    }
+    let summary = reports.iter().zip(&external)
+        .map(|(report, (_, external_deps))| TargetSummary {
+            target: report.target.clone(),
+            dependents: report.crawl.max_deps,
+            collected: report.crawl.dependents.len(),
+            external: external_deps.len(),
+            shared: shared.get(report.target.as_str()).copied().unwrap_or(0),
+        })
+        .collect();
+
+    let leaderboard = rollup.into_iter()
+        .filter(|entry| entry.targets.len() >= min_targets)
+        .collect();
+
+    Ok(BatchReport {
+        reports,
+        summary,
+        leaderboard,
+        errors,
+    })
+}
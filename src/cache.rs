@@ -1,7 +1,7 @@
 use std::fs;
 use std::path::PathBuf;
-use std::time::SystemTime;
-use dirs::cache_dir;
+use std::time::{Duration, SystemTime};
+use dirs::{cache_dir, data_dir};
 use crypto::digest::Digest;
 use crypto::md5::Md5;
 use flate2::read::GzDecoder;
@@ -12,7 +12,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::error::AppError;
 
-const CACHE_EXPIRY_HOURS: u64 = 24;
+pub const CACHE_EXPIRY_HOURS: u64 = 24;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CachedResponse {
@@ -24,11 +24,18 @@ pub fn get_cache_dir() -> PathBuf {
     let mut cache_path = cache_dir().unwrap_or_else(|| PathBuf::from("./cache"));
     cache_path.push("gh_get_dependent");
     fs::create_dir_all(&cache_path).unwrap_or_else(|_| {
-        println!("Warning: Could not create cache directory");
+        eprintln!("Warning: Could not create cache directory");
     });
     cache_path
 }
 
+/// Directory for data kept across runs, such as snapshots and trends.
+pub fn get_data_dir() -> PathBuf {
+    let mut data_path = data_dir().unwrap_or_else(|| PathBuf::from("./data"));
+    data_path.push("ghtopdep");
+    data_path
+}
+
 pub fn get_cache_path(url: &str) -> PathBuf {
     let mut hasher = Md5::new();
     hasher.input_str(url);
@@ -40,6 +47,10 @@ pub fn get_cache_path(url: &str) -> PathBuf {
 }
 
 pub fn is_cache_valid(cache_path: &PathBuf) -> bool {
+    is_cache_valid_for(cache_path, Duration::from_secs(CACHE_EXPIRY_HOURS * 3600))
+}
+
+pub fn is_cache_valid_for(cache_path: &PathBuf, ttl: Duration) -> bool {
     if !cache_path.exists() {
         return false;
     }
@@ -56,18 +67,22 @@ pub fn is_cache_valid(cache_path: &PathBuf) -> bool {
     
     let now = SystemTime::now();
     match now.duration_since(modified) {
-        Ok(duration) => duration.as_secs() < CACHE_EXPIRY_HOURS * 3600,
+        Ok(duration) => duration < ttl,
         Err(_) => false,
     }
 }
 
 pub fn read_from_cache(cache_path: &PathBuf) -> Result<String, AppError> {
     let file = fs::File::open(cache_path)?;
+    let corrupt = |source: AppError| AppError::CacheCorrupt {
+        path: cache_path.clone(),
+        source: Box::new(source),
+    };
     let mut decoder = GzDecoder::new(file);
     let mut cached_data = String::new();
-    decoder.read_to_string(&mut cached_data)?;
+    decoder.read_to_string(&mut cached_data).map_err(|e| corrupt(e.into()))?;
     
-    let cached: CachedResponse = serde_json::from_str(&cached_data)?;
+    let cached: CachedResponse = serde_json::from_str(&cached_data).map_err(|e| corrupt(e.into()))?;
     Ok(cached.content)
 }
 
@@ -86,4 +101,35 @@ pub fn write_to_cache(cache_path: &PathBuf, content: &str) -> Result<(), AppErro
     encoder.write_all(json.as_bytes())?;
     
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// Returns the number of cached responses and their total size in bytes.
+pub fn cache_stats() -> Result<(usize, u64), AppError> {
+    let mut entries = 0;
+    let mut total_size = 0;
+    
+    for entry in fs::read_dir(get_cache_dir())? {
+        let entry = entry?;
+        if entry.path().extension().is_some_and(|ext| ext == "json") {
+            entries += 1;
+            total_size += entry.metadata()?.len();
+        }
+    }
+    
+    Ok((entries, total_size))
+}
+
+/// Removes every cached response and returns how many were deleted.
+pub fn clear_cache() -> Result<usize, AppError> {
+    let mut removed = 0;
+    
+    for entry in fs::read_dir(get_cache_dir())? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            fs::remove_file(&path)?;
+            removed += 1;
+        }
+    }
+    
+    Ok(removed)
+}
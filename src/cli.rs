@@ -0,0 +1,514 @@
+use std::ffi::OsString;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+use clap::builder::RangedU64ValueParser;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use regex::Regex;
+
+use crate::config::{DEFAULT_CRAWL_BUDGET, DEFAULT_EXPAND_MIN_STARS};
+use crate::dependent::TypeSelection;
+use crate::error::AppError;
+use crate::filter::Filter;
+use crate::graph::GraphFormat;
+use crate::output::{Column, OutputFormat, TrendFormat};
+use crate::progress::ProgressMode;
+use crate::template::Template;
+
+#[derive(Debug, Parser)]
+#[command(name = "ghtopdep-rs", version, about = "Get top dependent repositories for a GitHub project")]
+#[command(arg_required_else_help = true)]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+
+    #[command(subcommand)]
+    pub command: Commands,
+}
+
+/// Options accepted by every subcommand.
+#[derive(Debug, Args)]
+pub struct GlobalArgs {
+    /// Disable caching
+    #[arg(long = "no-cache", global = true)]
+    pub no_cache: bool,
+
+    /// Do not record dependents counts for `trend`
+    #[arg(long = "no-trend", global = true)]
+    pub no_trend: bool,
+
+    /// Progress display
+    #[arg(long, global = true, value_enum, default_value_t = ProgressMode::Auto)]
+    pub progress: ProgressMode,
+
+    /// Suppress progress and status messages
+    #[arg(short, long, global = true, conflicts_with = "progress")]
+    pub quiet: bool,
+
+    /// Settings profile from the config files [env: GHTOPDEP_PROFILE]
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Cache expiry in hours [default: 24]
+    #[arg(long = "cache-ttl", global = true, value_name = "HOURS")]
+    pub cache_ttl: Option<u64>,
+
+    /// Shell command that prints a GitHub token
+    #[arg(long = "token-command", global = true, value_name = "COMMAND")]
+    pub token_command: Option<String>,
+
+    /// GitHub base URL [default: https://github.com]
+    #[arg(long = "base-url", global = true, value_name = "URL")]
+    pub base_url: Option<String>,
+
+    /// TOML file overriding the built-in CSS selectors, see `selectors show`
+    #[arg(long, global = true, value_name = "PATH")]
+    pub selectors: Option<PathBuf>,
+
+    /// Directory to save pages GitHub served in an unexpected shape
+    /// [default: <data dir>/ghtopdep/debug]
+    #[arg(long = "debug-dir", global = true, value_name = "DIR")]
+    pub debug_dir: Option<PathBuf>,
+
+    /// Proxy for every request [default: HTTPS_PROXY or HTTP_PROXY]
+    #[arg(long, global = true, value_name = "URL")]
+    pub proxy: Option<String>,
+
+    /// PEM bundle of root certificates to trust besides the system ones
+    #[arg(long = "ca-cert", global = true, value_name = "PATH")]
+    pub ca_cert: Option<PathBuf>,
+
+    /// PEM client certificate for TLS client authentication, followed by its
+    /// key unless --client-key is given
+    #[arg(long = "client-cert", global = true, value_name = "PATH")]
+    pub client_cert: Option<PathBuf>,
+
+    /// PKCS#8 PEM key of --client-cert
+    #[arg(long = "client-key", global = true, value_name = "PATH")]
+    pub client_key: Option<PathBuf>,
+
+    /// Seconds to wait for a connection [default: 10]
+    #[arg(long = "connect-timeout", global = true, value_name = "SECS")]
+    pub connect_timeout: Option<u64>,
+
+    /// Seconds to wait for a whole response [default: 60]
+    #[arg(long, global = true, value_name = "SECS")]
+    pub timeout: Option<u64>,
+
+    /// User-Agent header sent with every request [default: a desktop Chrome]
+    #[arg(long = "user-agent", global = true, value_name = "STRING")]
+    pub user_agent: Option<String>,
+
+    /// Record every response fetched into this fixture directory (disables the cache)
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Answer every request from a fixture directory made with --record
+    #[arg(long, global = true, value_name = "DIR")]
+    pub replay: Option<PathBuf>,
+
+    /// Write Prometheus metrics to this file when the command finishes, e.g.
+    /// for the node exporter's textfile collector
+    #[arg(long = "metrics-file", global = true, value_name = "PATH")]
+    pub metrics_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// Show the top dependents sorted by stars (default when no subcommand is given)
+    Top(TopArgs),
+    /// List every dependent above the star threshold
+    List(QueryArgs),
+    /// Print the number of dependents reported by GitHub
+    Count(TargetArgs),
+    /// List the packages published by a repository
+    Packages(PackagesArgs),
+    /// Run `top` for several repositories and rank dependents shared between them
+    Batch(BatchArgs),
+    /// Compare two snapshots saved with --snapshot
+    Diff(DiffArgs),
+    /// Re-crawl a repository periodically and report new dependents and star milestones
+    Watch(WatchArgs),
+    /// Show how the dependents counts recorded by previous runs changed over time
+    Trend(TrendArgs),
+    /// Serve dependents reports as JSON over HTTP
+    Serve(ServeArgs),
+    /// Inspect or clear the response cache
+    Cache(CacheArgs),
+    /// Print the CSS selectors or check them against a saved page
+    Selectors(SelectorsArgs),
+}
+
+/// The repository whose dependents are crawled.
+#[derive(Debug, Args)]
+pub struct TargetArgs {
+    /// GitHub repository URL or owner/repo format
+    pub repo_url: String,
+
+    /// Show package dependents instead of repositories (shorthand for --type packages)
+    #[arg(long)]
+    pub packages: bool,
+
+    /// Dependents lists to crawl [default: repositories]
+    #[arg(long = "type", value_enum, conflicts_with = "packages")]
+    pub dependent_types: Option<TypeSelection>,
+
+    /// Package to show dependents of, for repositories publishing several packages
+    #[arg(long, value_name = "NAME")]
+    pub package: Option<String>,
+
+    /// Crawl every package of the repository and report each separately
+    #[arg(long = "all-packages", conflicts_with = "package")]
+    pub all_packages: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct TopArgs {
+    /// Number of top dependents to fetch [default: 10]
+    #[arg(long = "rows", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub top_n: Option<usize>,
+
+    /// Levels of dependents to crawl: 2 adds the dependents of the top dependents, and so on
+    #[arg(long, default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub depth: usize,
+
+    /// With --depth, only crawl the dependents of repositories with at least this many stars
+    #[arg(long = "expand-minstar", default_value_t = DEFAULT_EXPAND_MIN_STARS, value_parser = parse_min_stars)]
+    pub expand_min_stars: f64,
+
+    /// With --depth, the most dependents lists to crawl in total
+    #[arg(long, default_value_t = DEFAULT_CRAWL_BUDGET, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub budget: usize,
+
+    /// Print the dependents and their relationships as a graph instead of a report
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub graph: Option<GraphFormat>,
+
+    #[command(flatten)]
+    pub query: QueryArgs,
+}
+
+/// Crawl and output options shared by `top` and `list`.
+#[derive(Debug, Args)]
+pub struct QueryArgs {
+    #[command(flatten)]
+    pub target: TargetArgs,
+
+    #[command(flatten)]
+    pub crawl: CrawlArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+/// Options controlling which dependents are collected.
+#[derive(Debug, Args)]
+pub struct CrawlArgs {
+    /// Maximum number of pages to fetch
+    #[arg(long = "max_pages", default_value_t = 100, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub max_pages: usize,
+
+    /// Minimum number of stars [default: 0]
+    #[arg(long = "minstar", value_parser = parse_min_stars)]
+    pub min_stars: Option<f64>,
+
+    /// Show repository descriptions
+    #[arg(long)]
+    pub description: bool,
+
+    /// Save the collected dependents as a timestamped snapshot for `diff`
+    #[arg(long)]
+    pub snapshot: bool,
+
+    /// Only keep dependents matching an expression, e.g. "stars >= 100 && name !~ /fork|mirror/"
+    #[arg(long, value_name = "EXPR", value_parser = parse_filter)]
+    pub filter: Option<Filter>,
+
+    /// Comma separated owners whose repositories are left out
+    #[arg(long = "exclude-owner", value_name = "OWNERS", value_delimiter = ',')]
+    pub exclude_owner: Vec<String>,
+
+    /// Only keep dependents whose owner/name matches this regex
+    #[arg(long = "include-regex", value_name = "REGEX", value_parser = parse_regex)]
+    pub include_regex: Option<Regex>,
+
+    /// Leave out the repositories (owner/name) and owners listed in a file, one per line
+    #[arg(long = "exclude-file", value_name = "PATH")]
+    pub exclude_file: Option<PathBuf>,
+
+    /// Check dependents for forks and mirrors and count them under their upstream
+    #[arg(long = "collapse-forks")]
+    pub collapse_forks: bool,
+
+    /// Read each dependent's page for its topics, license, language, archived status, homepage and last commit
+    #[arg(long)]
+    pub metadata: bool,
+
+    /// Leave out archived repositories
+    #[arg(long = "exclude-archived")]
+    pub exclude_archived: bool,
+
+    /// Only keep repositories with a commit within this long, e.g. "1year"
+    #[arg(long = "active-within", value_name = "DURATION", value_parser = parse_interval)]
+    pub active_within: Option<Duration>,
+}
+
+/// Options controlling how results are rendered.
+#[derive(Debug, Args)]
+pub struct OutputArgs {
+    /// Output format [default: table, or text with --template]
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Use table output format (shorthand for --format table)
+    #[arg(long, conflicts_with = "format")]
+    pub table: bool,
+
+    /// Comma separated columns for table and text output
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub columns: Vec<Column>,
+
+    /// Format string for each dependent in text output, e.g. "{rank}. {repo} {stars}"
+    #[arg(long, value_parser = parse_template, conflicts_with_all = ["table", "columns"])]
+    pub template: Option<Template>,
+
+    /// With --type both, show one list sorted by stars instead of a section per type
+    #[arg(long)]
+    pub merge: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct PackagesArgs {
+    /// GitHub repository URL or owner/repo format
+    pub repo_url: String,
+}
+
+#[derive(Debug, Args)]
+pub struct BatchArgs {
+    /// Repositories (owner/repo or URL) or files listing one repository per line
+    #[arg(required_unless_present = "org")]
+    pub targets: Vec<String>,
+
+    /// Also process every public repository of this organization, skipping forks and archived ones
+    #[arg(long, value_name = "OWNER")]
+    pub org: Option<String>,
+
+    /// Show package dependents instead of repositories
+    #[arg(long)]
+    pub packages: bool,
+
+    /// Number of top dependents to show per target [default: 10]
+    #[arg(long = "rows", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub top_n: Option<usize>,
+
+    /// Minimum number of targets a dependent must use to appear in the leaderboard [default: 2, or 1 with --org]
+    #[arg(long = "min-targets", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub min_targets: Option<usize>,
+
+    #[command(flatten)]
+    pub crawl: CrawlArgs,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    #[command(flatten)]
+    pub target: TargetArgs,
+
+    /// Older snapshot, as a file or snapshot id [default: the one before --to]
+    #[arg(long, value_name = "SNAPSHOT")]
+    pub from: Option<String>,
+
+    /// Newer snapshot, as a file or snapshot id [default: the latest]
+    #[arg(long, value_name = "SNAPSHOT")]
+    pub to: Option<String>,
+
+    /// Output format [default: table]
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+}
+
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub target: TargetArgs,
+
+    /// Time between crawls, e.g. 30m, 6h or 1day
+    #[arg(long, default_value = "6h", value_parser = parse_interval)]
+    pub interval: Duration,
+
+    /// Only report new dependents with at least this many stars [default: 0]
+    #[arg(long = "notify-min-stars", value_name = "N", value_parser = parse_min_stars)]
+    pub notify_min_stars: Option<f64>,
+
+    /// Comma separated star counts to report when a dependent reaches them
+    #[arg(long = "thresholds", value_name = "LIST", value_delimiter = ',', value_parser = parse_min_stars)]
+    pub thresholds: Vec<f64>,
+
+    /// POST events as JSON to this URL in addition to printing them
+    #[arg(long, value_name = "URL", value_parser = parse_webhook)]
+    pub webhook: Option<String>,
+
+    /// Print events as JSON lines
+    #[arg(long)]
+    pub json: bool,
+
+    /// Serve Prometheus metrics on this address at /metrics
+    #[arg(long = "metrics-bind", value_name = "ADDR")]
+    pub metrics_bind: Option<SocketAddr>,
+
+    #[command(flatten)]
+    pub crawl: CrawlArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub bind: SocketAddr,
+
+    /// Refresh results older than this in the background, e.g. 30m or 6h
+    #[arg(long = "refresh-after", value_name = "DURATION", default_value = "1h", value_parser = parse_interval)]
+    pub refresh_after: Duration,
+
+    /// Default number of dependents per response [default: 10]
+    #[arg(long = "rows", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub top_n: Option<usize>,
+
+    #[command(flatten)]
+    pub crawl: CrawlArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct TrendArgs {
+    /// GitHub repository URL or owner/repo format
+    pub repo_url: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = TrendFormat::Table)]
+    pub format: TrendFormat,
+
+    /// Only show counts recorded within this period, e.g. 30d or 1year
+    #[arg(long, value_name = "PERIOD", value_parser = parse_interval)]
+    pub since: Option<Duration>,
+}
+
+#[derive(Debug, Args)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub action: CacheAction,
+}
+
+#[derive(Debug, Args)]
+pub struct SelectorsArgs {
+    #[command(subcommand)]
+    pub action: SelectorsAction,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SelectorsAction {
+    /// Print the selectors in use as a selectors file
+    Show,
+    /// Report what the selectors find in a saved dependents or repository page
+    Test {
+        /// HTML file, e.g. saved from the browser or to --debug-dir
+        html_file: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CacheAction {
+    /// Print the cache directory
+    Path,
+    /// Show the number and total size of cached responses
+    Stats,
+    /// Remove all cached responses
+    Clear,
+}
+
+fn parse_min_stars(value: &str) -> Result<f64, String> {
+    let stars = value.parse::<f64>()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if !stars.is_finite() || stars < 0.0 {
+        return Err("must be a non-negative number".to_string());
+    }
+    Ok(stars)
+}
+
+fn parse_interval(value: &str) -> Result<Duration, String> {
+    let interval = humantime::parse_duration(value).map_err(|e| e.to_string())?;
+    if interval.is_zero() {
+        return Err("must be greater than zero".to_string());
+    }
+    Ok(interval)
+}
+
+fn parse_webhook(value: &str) -> Result<String, String> {
+    if !value.starts_with("http://") && !value.starts_with("https://") {
+        return Err("must start with http:// or https://".to_string());
+    }
+    Ok(value.to_string())
+}
+
+fn parse_filter(value: &str) -> Result<Filter, String> {
+    value.parse::<Filter>().map_err(|e| match e {
+        AppError::Other(message) => message,
+        e => e.to_string(),
+    })
+}
+
+fn parse_regex(value: &str) -> Result<Regex, String> {
+    Regex::new(value).map_err(|e| e.to_string())
+}
+
+fn parse_template(value: &str) -> Result<Template, String> {
+    value.parse::<Template>().map_err(|e| match e {
+        AppError::Other(message) => message,
+        e => e.to_string(),
+    })
+}
+
+/// Inserts the `top` subcommand when the first positional argument is not a
+/// known subcommand, so `ghtopdep-rs owner/repo --rows 5` keeps working.
+pub fn normalize_args<I, T>(args: I) -> Vec<OsString>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+
+    let command = Cli::command();
+    let mut subcommands = vec!["help".to_string()];
+    for sub in command.get_subcommands() {
+        subcommands.push(sub.get_name().to_string());
+        subcommands.extend(sub.get_all_aliases().map(str::to_string));
+    }
+
+    // Global options that take a separate value, e.g. `--progress plain`
+    let valued_options: Vec<String> = command.get_arguments()
+        .filter(|arg| arg.get_action().takes_values())
+        .filter_map(|arg| arg.get_long().map(|long| format!("--{}", long)))
+        .collect();
+
+    let mut needs_top = false;
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        let arg = arg.to_string_lossy();
+        match arg.as_ref() {
+            "-h" | "--help" | "-V" | "--version" => break,
+            _ if valued_options.iter().any(|option| *option == arg) => { iter.next(); },
+            _ if arg.starts_with('-') => {},
+            _ => {
+                needs_top = !subcommands.iter().any(|name| *name == arg);
+                break;
+            }
+        }
+    }
+
+    if needs_top {
+        args.insert(1, OsString::from("top"));
+    }
+    args
+}
@@ -1,23 +1,147 @@
-use reqwest::{Client, header};
-use tokio::time::{sleep, Duration};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use reqwest::{header, Certificate, Client, Identity, NoProxy, Proxy, StatusCode};
+use tokio::sync::{Mutex, Semaphore, SemaphorePermit};
+use tokio::time::{sleep, sleep_until, Duration, Instant};
 use crate::error::AppError;
+use crate::fetcher::Fetcher;
+use crate::fixtures::FixtureMode;
+use crate::metrics::{metrics, RetryReason};
+
+/// The `User-Agent` sent unless another one is configured.
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36";
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Settings applied to every request made by a client.
+#[derive(Debug, Default, Clone)]
+pub struct ClientOptions {
+    /// GitHub token sent as an `Authorization` header
+    pub token: Option<String>,
+    /// Record responses to, or replay them from, a fixture directory
+    pub fixtures: Option<FixtureMode>,
+    /// Proxy for every request, instead of `HTTPS_PROXY` and `HTTP_PROXY`
+    pub proxy: Option<String>,
+    /// PEM bundle of root certificates trusted besides the system ones
+    pub ca_cert: Option<PathBuf>,
+    /// PEM certificate chain presented to servers asking for one
+    pub client_cert: Option<PathBuf>,
+    /// PKCS#8 PEM key of `client_cert`, when it is not in the same file
+    pub client_key: Option<PathBuf>,
+    /// Most time to wait for a connection [default: 10s]
+    pub connect_timeout: Option<Duration>,
+    /// Most time to wait for a whole response, from sending the request
+    /// [default: 60s]
+    pub timeout: Option<Duration>,
+    /// [default: [`DEFAULT_USER_AGENT`]]
+    pub user_agent: Option<String>,
+}
+
+/// Paces sequential page requests and caps concurrent requests for every
+/// crawl sharing it.
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+    permits: Semaphore,
+}
+
+impl RateLimiter {
+    pub fn new(interval: Duration, max_concurrent: usize) -> Self {
+        RateLimiter {
+            interval,
+            next_slot: Mutex::new(Instant::now()),
+            permits: Semaphore::new(max_concurrent),
+        }
+    }
+
+    /// Waits until at least `interval` has passed since the previous call.
+    pub async fn wait(&self) {
+        let mut next_slot = self.next_slot.lock().await;
+        sleep_until(*next_slot).await;
+        *next_slot = Instant::now() + self.interval;
+    }
+
+    /// Holds one of the concurrent request slots until the permit is dropped.
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        self.permits.acquire().await.expect("rate limiter semaphore is never closed")
+    }
+}
 
 pub fn create_client() -> Result<Client, AppError> {
+    create_client_with(&ClientOptions::default())
+}
+
+pub fn create_client_with(options: &ClientOptions) -> Result<Client, AppError> {
     let mut headers = header::HeaderMap::new();
+    let user_agent = options.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
     headers.insert(
         header::USER_AGENT,
-        header::HeaderValue::from_static("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36"),
+        header::HeaderValue::from_str(user_agent)
+            .map_err(|_| AppError::Other(format!("Invalid User-Agent '{}'", user_agent)))?,
     );
     
-    let client = Client::builder()
+    if let Some(token) = &options.token {
+        let mut value = header::HeaderValue::from_str(&format!("token {}", token))
+            .map_err(|_| AppError::Other("GitHub token contains invalid characters".to_string()))?;
+        value.set_sensitive(true);
+        headers.insert(header::AUTHORIZATION, value);
+    }
+    
+    let mut builder = Client::builder()
         .default_headers(headers)
-        .build()?;
+        .connect_timeout(options.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT))
+        .timeout(options.timeout.unwrap_or(DEFAULT_TIMEOUT));
     
-    Ok(client)
+    // Without a proxy option, reqwest uses HTTPS_PROXY, HTTP_PROXY and NO_PROXY
+    if let Some(url) = &options.proxy {
+        let proxy = Proxy::all(url.as_str())
+            .map_err(|e| AppError::Other(format!("Invalid proxy '{}': {}", url, e)))?
+            .no_proxy(NoProxy::from_env());
+        builder = builder.proxy(proxy);
+    }
+    
+    if let Some(path) = &options.ca_cert {
+        let certificates = Certificate::from_pem_bundle(&read_pem(path)?)
+            .map_err(|e| AppError::Other(format!("Invalid CA bundle {}: {}", path.display(), e)))?;
+        if certificates.is_empty() {
+            return Err(AppError::Other(format!("No certificates in CA bundle {}", path.display())));
+        }
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    
+    match (&options.client_cert, &options.client_key) {
+        (Some(cert_path), key_path) => {
+            let cert = read_pem(cert_path)?;
+            // A single file can hold both the chain and the key
+            let key = match key_path {
+                Some(key_path) => read_pem(key_path)?,
+                None => cert.clone(),
+            };
+            let identity = Identity::from_pkcs8_pem(&cert, &key)
+                .map_err(|e| AppError::Other(format!("Invalid client certificate {}: {}", cert_path.display(), e)))?;
+            builder = builder.identity(identity);
+        },
+        (None, Some(_)) => {
+            return Err(AppError::Other("A client key needs a client certificate".to_string()));
+        },
+        (None, None) => {},
+    }
+    
+    Ok(builder.build()?)
+}
+
+fn read_pem(path: &Path) -> Result<Vec<u8>, AppError> {
+    fs::read(path).map_err(|e| AppError::Other(format!("Could not read {}: {}", path.display(), e)))
 }
 
+/// GETs `url`, retrying rate limited requests and failed connections with
+/// exponential backoff. Rate limited requests wait at least as long as
+/// GitHub's `Retry-After`.
 pub async fn fetch_with_retry(
-    client: &Client, 
+    fetcher: &dyn Fetcher, 
     url: &str, 
     max_retries: usize
 ) -> Result<String, AppError> {
@@ -25,39 +149,69 @@ pub async fn fetch_with_retry(
     let mut delay = 1;
     
     loop {
-        match client.get(url).send().await {
+        match fetcher.get(url).await {
             Ok(response) => {
-                if response.status().is_success() {
-                    return Ok(response.text().await?);
-                } else if response.status().as_u16() == 429 {
-                    // Rate limited - exponential backoff
+                metrics().record_response(response.status);
+                if response.is_success() {
+                    metrics().record_page(response.body.len());
+                    return Ok(response.body);
+                } else if response.status == StatusCode::TOO_MANY_REQUESTS.as_u16() {
+                    // Rate limited - exponential backoff, or longer when GitHub asks for it
                     if retries >= max_retries {
-                        return Err(AppError::Other(format!(
-                            "Rate limited after {} retries", retries
-                        )));
+                        return Err(AppError::RateLimited { retry_after: response.retry_after });
                     }
                     
-                    println!("Rate limited, retrying in {} seconds...", delay);
-                    sleep(Duration::from_secs(delay)).await;
+                    let wait = response.retry_after.map_or(Duration::from_secs(delay), |retry_after| {
+                        retry_after.max(Duration::from_secs(delay))
+                    });
+                    eprintln!("Rate limited, retrying in {} seconds...", wait.as_secs());
+                    metrics().record_retry(RetryReason::RateLimited);
+                    sleep(wait).await;
                     delay *= 2; // Exponential backoff
                     retries += 1;
                     continue;
+                } else if response.status == StatusCode::NOT_FOUND.as_u16() {
+                    return Err(AppError::NotFound { url: url.to_string() });
                 } else {
-                    return Err(AppError::Other(format!(
-                        "HTTP error: {}", response.status()
-                    )));
+                    return Err(AppError::HttpStatus {
+                        code: response.status,
+                        url: url.to_string(),
+                    });
                 }
             },
             Err(e) => {
                 if retries >= max_retries {
-                    return Err(e.into());
+                    return Err(e);
                 }
                 
-                println!("Network error, retrying in {} seconds: {}", delay, e);
+                eprintln!("Network error, retrying in {} seconds: {}", delay, e);
+                metrics().record_retry(RetryReason::Network);
                 sleep(Duration::from_secs(delay)).await;
                 delay *= 2;
                 retries += 1;
             }
         }
     }
-} 
\ No newline at end of file
+}
+
+/// Runs a shell command such as `gh auth token` and returns the token it prints.
+pub fn run_token_command(command: &str) -> Result<String, AppError> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", command]).output()?
+    } else {
+        Command::new("sh").args(["-c", command]).output()?
+    };
+    
+    if !output.status.success() {
+        return Err(AppError::Other(format!(
+            "Token command '{}' failed with {}", command, output.status
+        )));
+    }
+    
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if token.is_empty() {
+        return Err(AppError::Other(format!("Token command '{}' printed no token", command)));
+    }
+    
+    Ok(token)
+}
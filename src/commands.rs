@@ -0,0 +1,379 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use clap::error::ErrorKind;
+use clap::CommandFactory;
+
+use crate::batch::{read_targets, run_batch};
+use crate::cache::{cache_stats, clear_cache, get_cache_dir};
+use crate::cli::{BatchArgs, CacheAction, Cli, Commands, SelectorsAction};
+use crate::config::{Config, DEFAULT_MIN_STARS};
+use crate::dependent::TypeSelection;
+use crate::error::AppError;
+use crate::github::{
+    classify_page, fetch_header_counts, list_org_repos, merge_crawls, parse_header_counts, parse_packages,
+    parse_page, parse_repo_page, Crawler, PageKind,
+};
+use crate::graph::render_graph;
+use crate::metrics::{metrics, write_textfile};
+use crate::output::{
+    display_batch, display_diff, display_packages, display_results, display_sections, display_tree, display_trend,
+    Section, TrendFormat,
+};
+use crate::selectors::{install_selectors, selectors, Selectors};
+use crate::serve::{run_serve, ServeOptions};
+use crate::settings::{load_settings, Settings};
+use crate::snapshot::{
+    default_snapshot_root, diff_snapshots, list_snapshots, resolve_snapshot, save_crawl, Snapshot,
+};
+use crate::tree::build_trees;
+use crate::trend::{default_trend_root, load_points, record_counts};
+use crate::watch::{run_watch, WatchOptions};
+
+pub async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let settings = load_settings(&cli.global).map_err(usage_error)?;
+    if let Some(path) = &settings.selectors {
+        install_selectors(Selectors::load(path).map_err(usage_error)?)?;
+    }
+    
+    let result = run_command(&cli, &settings).await;
+    // Failed runs are written too, so the collector sees their requests
+    if let Some(path) = &cli.global.metrics_file {
+        if let Err(e) = write_textfile(metrics(), path) {
+            eprintln!("Warning: Could not write metrics to {}: {}", path.display(), e);
+        }
+    }
+    result
+}
+
+async fn run_command(cli: &Cli, settings: &Settings) -> Result<(), Box<dyn std::error::Error>> {
+    match &cli.command {
+        Commands::Top(args) => {
+            let config = Config::from_args(&cli.global, settings, args).map_err(usage_error)?;
+            run_top(&config).await
+        },
+        Commands::List(args) => {
+            let config = Config::from_query(&cli.global, settings, args, usize::MAX)
+                .map_err(usage_error)?;
+            run_top(&config).await
+        },
+        Commands::Count(args) => {
+            let config = Config::for_target(&cli.global, settings, args).map_err(usage_error)?;
+            run_count(&config).await
+        },
+        Commands::Packages(args) => {
+            let config = Config::for_repo(&cli.global, settings, &args.repo_url).map_err(usage_error)?;
+            run_packages(&config).await
+        },
+        Commands::Batch(args) => {
+            let config = Config::from_batch(&cli.global, settings, args).map_err(usage_error)?;
+            run_batch_command(&config, args).await
+        },
+        Commands::Diff(args) => {
+            let config = Config::from_diff(&cli.global, settings, args).map_err(usage_error)?;
+            run_diff(&config, args.from.as_deref(), args.to.as_deref())
+        },
+        Commands::Watch(args) => {
+            let config = Config::from_watch(&cli.global, settings, args).map_err(usage_error)?;
+            let options = WatchOptions {
+                interval: args.interval,
+                min_stars: args.notify_min_stars.unwrap_or(DEFAULT_MIN_STARS),
+                thresholds: args.thresholds.clone(),
+                webhook: args.webhook.clone(),
+                json: args.json,
+                metrics_bind: args.metrics_bind,
+            };
+            Ok(run_watch(&config, &options).await?)
+        },
+        Commands::Trend(args) => {
+            let config = Config::for_repo(&cli.global, settings, &args.repo_url).map_err(usage_error)?;
+            run_trend(&config, args.format, args.since)
+        },
+        Commands::Serve(args) => {
+            let config = Config::from_serve(&cli.global, settings, args).map_err(usage_error)?;
+            let options = ServeOptions {
+                bind: args.bind,
+                refresh_after: args.refresh_after,
+            };
+            Ok(run_serve(&config, &options).await?)
+        },
+        Commands::Cache(args) => run_cache(&args.action),
+        Commands::Selectors(args) => run_selectors(&args.action),
+    }
+}
+
+/// Reports an invalid combination of arguments the same way clap reports
+/// parse errors, so both exit with a usage error status.
+pub fn usage_error(err: AppError) -> clap::Error {
+    let message = match err {
+        AppError::Other(message) => message,
+        err => err.to_string(),
+    };
+    Cli::command().error(ErrorKind::ArgumentConflict, message)
+}
+
+pub async fn run_top(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    if config.depth > 1 || config.graph_format.is_some() {
+        return run_tree(config).await;
+    }
+    if !config.quiet {
+        eprintln!("Fetching {} dependents for {}/{}...", 
+            config.type_label(), 
+            config.owner, 
+            config.repo);
+    }
+
+    let start_time = Instant::now();
+
+    let crawler = Crawler::new(&config.client_options()?)?;
+    let mut sections = Vec::new();
+    for package_config in crawler.package_configs(config).await? {
+        let type_configs = package_config.type_configs();
+        let mut crawls = Vec::new();
+        for (idx, type_config) in type_configs.iter().enumerate() {
+            let crawl = crawler.crawl(type_config).await?;
+            // Every dependents page shows the counts of both types; counts of
+            // a selected package are not the repository's
+            if idx == 0 && type_config.package_id.is_none() {
+                record_counts(config, crawl.header);
+            }
+            if config.save_snapshot {
+                save_crawl(type_config, &crawl)?;
+            }
+            crawls.push(crawl);
+        }
+        
+        if crawls.len() > 1 && !config.merge_types {
+            sections.extend(type_configs.into_iter().zip(crawls));
+        } else {
+            sections.push((package_config, merge_crawls(crawls)));
+        }
+    }
+    
+    let mut results = Vec::new();
+    for (section_config, crawl) in sections {
+        let top_dependents = crawler.top_dependents(&section_config, &crawl).await;
+        results.push(Section {
+            config: section_config,
+            top_dependents,
+            crawl,
+        });
+    }
+
+    let elapsed = start_time.elapsed();
+    
+    if let [section] = results.as_slice() {
+        display_results(
+            &section.config,
+            &section.top_dependents,
+            section.crawl.total_repos_count,
+            section.crawl.matched_count,
+            section.crawl.max_deps,
+            section.crawl.unique_upstreams,
+            elapsed.as_secs_f64(),
+        )?;
+    } else {
+        display_sections(config, &results, elapsed.as_secs_f64())?;
+    }
+
+    Ok(())
+}
+
+pub async fn run_tree(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    if !config.quiet {
+        eprintln!("Fetching {} dependents for {}/{}{}...", 
+            config.type_label(), 
+            config.owner, 
+            config.repo,
+            if config.depth > 1 { format!(" ({} levels)", config.depth) } else { String::new() });
+    }
+
+    let start_time = Instant::now();
+    let crawler = Crawler::new(&config.client_options()?)?;
+    let tree = build_trees(&crawler, config).await?;
+    match config.graph_format {
+        Some(format) => print!("{}", render_graph(&tree, format)),
+        None => display_tree(config, &tree, start_time.elapsed().as_secs_f64())?,
+    }
+    Ok(())
+}
+
+pub async fn run_batch_command(config: &Config, args: &BatchArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let crawler = Crawler::new(&config.client_options()?)?;
+    
+    let mut targets = if args.targets.is_empty() {
+        Vec::new()
+    } else {
+        read_targets(&args.targets)?
+    };
+    if let Some(org) = &args.org {
+        if !config.quiet {
+            eprintln!("Listing public repositories of {}...", org);
+        }
+        for repo in list_org_repos(crawler.fetcher(), &config.api_url(), org, config.cache_ttl()).await? {
+            if !targets.contains(&repo) {
+                targets.push(repo);
+            }
+        }
+    }
+    
+    let min_targets = args.min_targets.unwrap_or(if args.org.is_some() { 1 } else { 2 });
+    let batch = run_batch(&crawler, config, &targets, min_targets, args.org.as_deref()).await?;
+    display_batch(config, &batch)?;
+
+    if batch.reports.is_empty() {
+        eprintln!("No target could be processed");
+        let error = batch.most_severe_error()
+            .unwrap_or_else(|| AppError::Other("No target could be processed".to_string()));
+        return Err(Box::new(error));
+    }
+    Ok(())
+}
+
+pub async fn run_packages(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let crawler = Crawler::new(&config.client_options()?)?;
+    let packages = crawler.list_packages(config).await?;
+    
+    if packages.is_empty() {
+        println!("{}/{} does not list any packages", config.owner, config.repo);
+    } else {
+        display_packages(config, &packages)?;
+    }
+    Ok(())
+}
+
+pub async fn run_count(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let crawler = Crawler::new(&config.client_options()?)?;
+    let package_configs = crawler.package_configs(config).await?;
+    
+    for package_config in &package_configs {
+        let page_url = package_config.first_page_url();
+        let counts = fetch_header_counts(crawler.fetcher(), &page_url, config.cache_ttl()).await?;
+        if package_config.package_id.is_none() {
+            record_counts(config, counts);
+        }
+        
+        let prefix = match &package_config.package {
+            Some(package) if config.all_packages => format!("{}: ", package),
+            _ => String::new(),
+        };
+        if config.type_selection == TypeSelection::Both {
+            println!("{}{} repositories", prefix, counts.repositories.unwrap_or(0));
+            println!("{}{} packages", prefix, counts.packages.unwrap_or(0));
+        } else {
+            println!("{}{}", prefix, counts.for_type(config.dependent_type()).unwrap_or(0));
+        }
+    }
+    
+    Ok(())
+}
+
+/// Compares two snapshots of the configured repository. Without `--to` the
+/// latest snapshot is used, and without `--from` the one taken before it.
+pub fn run_diff(config: &Config, from: Option<&str>, to: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let root = default_snapshot_root();
+    let snapshots = list_snapshots(&root, config)?;
+    
+    let to_path = match to {
+        Some(spec) => resolve_snapshot(&root, config, spec)?,
+        None => snapshots.last().cloned().ok_or_else(|| AppError::Other(format!(
+            "No snapshots for {}/{}; save one with --snapshot", config.owner, config.repo
+        )))?,
+    };
+    let from_path = match from {
+        Some(spec) => resolve_snapshot(&root, config, spec)?,
+        None => snapshots.iter()
+            .take_while(|path| **path != to_path)
+            .last()
+            .cloned()
+            .ok_or_else(|| AppError::Other(format!(
+                "No earlier snapshot of {}/{} to compare with", config.owner, config.repo
+            )))?,
+    };
+    
+    let diff = diff_snapshots(&Snapshot::load(&from_path)?, &Snapshot::load(&to_path)?);
+    display_diff(config, &diff)?;
+    
+    Ok(())
+}
+
+pub fn run_trend(
+    config: &Config, 
+    format: TrendFormat, 
+    since: Option<Duration>
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut points = load_points(&default_trend_root(), &config.owner, &config.repo)?;
+    if let Some(since) = since {
+        let start = SystemTime::now().checked_sub(since).unwrap_or(UNIX_EPOCH);
+        let start = start.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        points.retain(|point| point.taken_at >= start);
+    }
+    
+    if points.is_empty() {
+        return Err(Box::new(AppError::Other(format!(
+            "No dependents counts recorded for {}/{}", config.owner, config.repo
+        ))));
+    }
+    
+    display_trend(format, &points)?;
+    Ok(())
+}
+
+pub fn run_cache(action: &CacheAction) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        CacheAction::Path => println!("{}", get_cache_dir().display()),
+        CacheAction::Stats => {
+            let (entries, total_size) = cache_stats()?;
+            println!("{} cached responses ({:.1} KiB) in {}", 
+                entries, 
+                total_size as f64 / 1024.0, 
+                get_cache_dir().display());
+        },
+        CacheAction::Clear => {
+            let removed = clear_cache()?;
+            println!("Removed {} cached responses", removed);
+        },
+    }
+    
+    Ok(())
+}
+
+/// Prints the selectors in use, or what they find in a saved page. A page
+/// they find nothing in is an error, so scripts can check a selectors file.
+pub fn run_selectors(action: &SelectorsAction) -> Result<(), Box<dyn std::error::Error>> {
+    let html_file = match action {
+        SelectorsAction::Show => {
+            print!("{}", selectors().profile.to_toml());
+            return Ok(());
+        },
+        SelectorsAction::Test { html_file } => html_file,
+    };
+    
+    let html = std::fs::read_to_string(html_file)?;
+    let document = scraper::Html::parse_document(&html);
+    println!("Matches in {}:", html_file.display());
+    for (key, selector, compiled) in selectors().entries() {
+        println!("  {:<16} {:>4}  {}", key, document.select(compiled).count(), selector);
+    }
+    
+    let kind = classify_page(&html);
+    let (dependents, next_link) = parse_page(&html);
+    let counts = parse_header_counts(&html);
+    let description = parse_repo_page(&html).description;
+    println!("Page: {}", kind.describe());
+    println!("Dependents: {}", dependents.len());
+    for (repo, stars, forks) in dependents.iter().take(5) {
+        println!("  {} (⭐ {}{})", repo, stars, forks.as_ref().map_or(String::new(), |forks| format!(", {} forks", forks)));
+    }
+    println!("Next page: {}", next_link.as_deref().unwrap_or("none"));
+    let count = |count: Option<usize>| count.map_or("unknown".to_string(), |count| count.to_string());
+    println!("Header counts: {} repositories, {} packages", count(counts.repositories), count(counts.packages));
+    println!("Packages: {}", parse_packages(&html).len());
+    println!("Description: {}", description.as_deref().unwrap_or("none"));
+    
+    if kind == PageKind::Unrecognized && description.is_none() {
+        return Err(Box::new(AppError::ParseFailure {
+            url: html_file.display().to_string(),
+            reason: "the selectors found neither dependents nor a description".to_string(),
+        }));
+    }
+    Ok(())
+}
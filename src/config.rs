@@ -1,54 +1,438 @@
-use clap::ArgMatches;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::cache::{get_data_dir, CACHE_EXPIRY_HOURS};
+use crate::cli::{
+    BatchArgs, CrawlArgs, DiffArgs, GlobalArgs, OutputArgs, QueryArgs, ServeArgs, TargetArgs, TopArgs, WatchArgs,
+};
+use crate::client::{run_token_command, ClientOptions};
+use crate::dependent::{DependentType, TypeSelection};
 use crate::error::AppError;
+use crate::filter::Filter;
+use crate::fixtures::FixtureMode;
+use crate::github::{PackageOption, GITHUB_API_URL, GITHUB_BASE_URL};
+use crate::graph::GraphFormat;
+use crate::output::{Column, OutputFormat};
+use crate::progress::ProgressMode;
+use crate::settings::Settings;
+use crate::template::Template;
 
-pub const DEFAULT_FORMAT: &str = "table";
+pub const DEFAULT_FORMAT: OutputFormat = OutputFormat::Table;
 pub const DEFAULT_MIN_STARS: f64 = 0.0;
+pub const DEFAULT_ROWS: usize = 10;
+pub const DEFAULT_EXPAND_MIN_STARS: f64 = 100.0;
+pub const DEFAULT_CRAWL_BUDGET: usize = 20;
 
+#[derive(Debug, Clone)]
 pub struct Config {
     pub owner: String,
     pub repo: String,
     pub top_n: usize,
     pub max_pages: usize,
     pub min_stars: f64,
+    /// Every filter must match for a dependent to be kept
+    pub filters: Vec<Filter>,
+    /// Check collected dependents for forks and collapse them under their upstream
+    pub collapse_forks: bool,
+    /// Read the page of every collected dependent for its metadata
+    pub fetch_metadata: bool,
+    /// Levels of dependents to crawl, 1 for direct dependents only
+    pub depth: usize,
+    /// Dependents below this many stars are not crawled for the next level
+    pub expand_min_stars: f64,
+    /// Most dependents lists crawled for one tree
+    pub crawl_budget: usize,
+    /// Print the dependents as a graph in this format instead of a report
+    pub graph_format: Option<GraphFormat>,
     pub is_package: bool,
+    pub type_selection: TypeSelection,
+    pub merge_types: bool,
+    /// Package selected with --package, by name
+    pub package: Option<String>,
+    /// `package_id` of the selected package, once resolved
+    pub package_id: Option<String>,
+    pub all_packages: bool,
     pub show_desc: bool,
+    pub save_snapshot: bool,
     pub use_cache: bool,
-    pub output_format: String,
+    pub record_trend: bool,
+    pub cache_ttl_hours: u64,
+    pub base_url: String,
+    pub token_command: Option<String>,
+    /// Where pages that could not be understood are saved, see [`Config::debug_dir`]
+    pub debug_dir: Option<PathBuf>,
+    /// Record responses to, or replay them from, a fixture directory
+    pub fixtures: Option<FixtureMode>,
+    /// Proxy, TLS, timeout and User-Agent options; the token and fixtures
+    /// are added by [`Config::client_options`]
+    pub network: ClientOptions,
+    pub output_format: OutputFormat,
+    pub columns: Option<Vec<Column>>,
+    pub template: Option<Template>,
+    pub progress: ProgressMode,
+    pub quiet: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            owner: String::new(),
+            repo: String::new(),
+            top_n: DEFAULT_ROWS,
+            max_pages: 100,
+            min_stars: DEFAULT_MIN_STARS,
+            filters: Vec::new(),
+            collapse_forks: false,
+            fetch_metadata: false,
+            depth: 1,
+            expand_min_stars: DEFAULT_EXPAND_MIN_STARS,
+            crawl_budget: DEFAULT_CRAWL_BUDGET,
+            graph_format: None,
+            is_package: false,
+            type_selection: TypeSelection::Repositories,
+            merge_types: false,
+            package: None,
+            package_id: None,
+            all_packages: false,
+            show_desc: false,
+            save_snapshot: false,
+            use_cache: true,
+            record_trend: false,
+            cache_ttl_hours: CACHE_EXPIRY_HOURS,
+            base_url: GITHUB_BASE_URL.to_string(),
+            token_command: None,
+            debug_dir: None,
+            fixtures: None,
+            network: ClientOptions::default(),
+            output_format: DEFAULT_FORMAT,
+            columns: None,
+            template: None,
+            progress: ProgressMode::Auto,
+            quiet: false,
+        }
+    }
 }
 
 impl Config {
-    pub fn from_matches(matches: &ArgMatches) -> Result<Self, AppError> {
-        let repo_url = matches.get_one::<String>("repo_url").unwrap();
-        let (owner, repo) = parse_repo_url(repo_url)?;
+    pub fn from_args(global: &GlobalArgs, settings: &Settings, args: &TopArgs) -> Result<Self, AppError> {
+        let top_n = args.top_n.or(settings.rows).unwrap_or(DEFAULT_ROWS);
+        Ok(Config {
+            depth: args.depth,
+            expand_min_stars: args.expand_min_stars,
+            crawl_budget: args.budget,
+            graph_format: args.graph,
+            ..Self::from_query(global, settings, &args.query, top_n)?
+        })
+    }
 
-        let top_n = matches.get_one::<String>("top_n").unwrap().parse::<usize>().unwrap_or(10);
-        let max_pages = matches.get_one::<String>("max_pages").unwrap().parse::<usize>().unwrap_or(100);
-        let min_stars = matches.get_one::<String>("min_stars").unwrap().parse::<f64>().unwrap_or(DEFAULT_MIN_STARS);
-        let is_package = matches.get_flag("packages");
-        let show_desc = matches.get_flag("description");
-        let use_cache = !matches.get_flag("no-cache");
+    pub fn from_query(
+        global: &GlobalArgs, 
+        settings: &Settings, 
+        query: &QueryArgs, 
+        top_n: usize
+    ) -> Result<Self, AppError> {
+        Ok(Config {
+            top_n,
+            ..Self::for_target(global, settings, &query.target)?
+                .with_crawl(settings, &query.crawl)?
+                .with_output(settings, &query.output)?
+        })
+    }
 
-        let output_format = if matches.get_flag("table") {
-            "table".to_string()
-        } else {
-            matches.get_one::<String>("format").unwrap().clone()
+    /// Builds a configuration for commands that only need the target repository.
+    pub fn for_target(global: &GlobalArgs, settings: &Settings, target: &TargetArgs) -> Result<Self, AppError> {
+        let type_selection = match target.dependent_types {
+            Some(selection) => selection,
+            None if target.packages => TypeSelection::Packages,
+            None => TypeSelection::Repositories,
         };
+        Ok(Config {
+            is_package: type_selection == TypeSelection::Packages,
+            type_selection,
+            package: target.package.clone(),
+            all_packages: target.all_packages,
+            ..Self::from_globals(global, settings)?.with_target(&target.repo_url)?
+        })
+    }
+
+    /// Builds the configuration shared by every target of a batch run; each
+    /// target is then selected with [`Config::with_target`].
+    pub fn from_batch(global: &GlobalArgs, settings: &Settings, args: &BatchArgs) -> Result<Self, AppError> {
+        Ok(Config {
+            top_n: args.top_n.or(settings.rows).unwrap_or(DEFAULT_ROWS),
+            is_package: args.packages,
+            type_selection: if args.packages { TypeSelection::Packages } else { TypeSelection::Repositories },
+            ..Self::from_globals(global, settings)?
+                .with_crawl(settings, &args.crawl)?
+                .with_output(settings, &args.output)?
+        })
+    }
+
+    pub fn from_diff(global: &GlobalArgs, settings: &Settings, args: &DiffArgs) -> Result<Self, AppError> {
+        Ok(Config {
+            output_format: args.format.or(settings.format).unwrap_or(DEFAULT_FORMAT),
+            ..Self::for_target(global, settings, &args.target)?.single_type("diff")?
+        })
+    }
+
+    pub fn from_watch(global: &GlobalArgs, settings: &Settings, args: &WatchArgs) -> Result<Self, AppError> {
+        Self::for_target(global, settings, &args.target)?
+            .single_type("watch")?
+            .with_crawl(settings, &args.crawl)
+    }
 
+    /// Builds the defaults of `serve`; requests pick the repository.
+    pub fn from_serve(global: &GlobalArgs, settings: &Settings, args: &ServeArgs) -> Result<Self, AppError> {
+        Ok(Config {
+            top_n: args.top_n.or(settings.rows).unwrap_or(DEFAULT_ROWS),
+            ..Self::from_globals(global, settings)?.with_crawl(settings, &args.crawl)?
+        })
+    }
+
+    /// Builds a configuration for commands that only take a repository.
+    pub fn for_repo(global: &GlobalArgs, settings: &Settings, repo_url: &str) -> Result<Self, AppError> {
+        Self::from_globals(global, settings)?.with_target(repo_url)
+    }
+
+    /// A copy of this configuration for another repository.
+    pub fn with_target(&self, repo_url: &str) -> Result<Self, AppError> {
+        let (owner, repo) = parse_repo_url(repo_url)?;
         Ok(Config {
             owner,
             repo,
-            top_n,
-            max_pages,
-            min_stars,
-            is_package,
-            show_desc,
-            use_cache,
+            ..self.clone()
+        })
+    }
+
+    /// One configuration per dependent type to crawl.
+    pub fn type_configs(&self) -> Vec<Config> {
+        self.type_selection.types().into_iter()
+            .map(|dependent_type| Config {
+                is_package: dependent_type == DependentType::Package,
+                ..self.clone()
+            })
+            .collect()
+    }
+
+    /// A copy of this configuration for one package of the repository.
+    pub fn with_package(&self, package: &PackageOption) -> Self {
+        Config {
+            package: Some(package.name.clone()),
+            package_id: Some(package.id.clone()),
+            all_packages: false,
+            ..self.clone()
+        }
+    }
+
+    fn single_type(self, command: &str) -> Result<Self, AppError> {
+        if self.type_selection == TypeSelection::Both {
+            return Err(AppError::Other(format!("--type both cannot be used with {}", command)));
+        }
+        if self.all_packages {
+            return Err(AppError::Other(format!("--all-packages cannot be used with {}", command)));
+        }
+        Ok(self)
+    }
+
+    fn from_globals(global: &GlobalArgs, settings: &Settings) -> Result<Self, AppError> {
+        let progress = if global.quiet {
+            ProgressMode::Never
+        } else {
+            global.progress
+        };
+
+        let fixtures = match (&global.record, &global.replay) {
+            (Some(dir), _) => Some(FixtureMode::Record(dir.clone())),
+            (None, Some(dir)) => Some(FixtureMode::Replay(dir.clone())),
+            (None, None) => None,
+        };
+        let replaying = matches!(fixtures, Some(FixtureMode::Replay(_)));
+
+        // Recorded crawls fetch every page; replayed ones are not cached or recorded as trends
+        Ok(Config {
+            use_cache: !global.no_cache && fixtures.is_none(),
+            record_trend: !global.no_trend && !replaying,
+            cache_ttl_hours: settings.cache_ttl.unwrap_or(CACHE_EXPIRY_HOURS),
+            base_url: settings.base_url.as_deref()
+                .unwrap_or(GITHUB_BASE_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            token_command: settings.token_command.clone(),
+            debug_dir: global.debug_dir.clone(),
+            fixtures,
+            network: ClientOptions {
+                proxy: settings.proxy.clone(),
+                ca_cert: settings.ca_cert.clone(),
+                client_cert: settings.client_cert.clone(),
+                client_key: settings.client_key.clone(),
+                connect_timeout: settings.connect_timeout.map(Duration::from_secs),
+                timeout: settings.timeout.map(Duration::from_secs),
+                user_agent: settings.user_agent.clone(),
+                ..ClientOptions::default()
+            },
+            progress,
+            quiet: global.quiet,
+            ..Config::default()
+        })
+    }
+
+    fn with_crawl(self, settings: &Settings, crawl: &CrawlArgs) -> Result<Self, AppError> {
+        let mut filters: Vec<Filter> = crawl.filter.iter().cloned().collect();
+        if !crawl.exclude_owner.is_empty() {
+            filters.push(Filter::exclude_owners(&crawl.exclude_owner));
+        }
+        if let Some(regex) = &crawl.include_regex {
+            filters.push(Filter::include_regex(regex.clone()));
+        }
+        if let Some(path) = &crawl.exclude_file {
+            filters.push(Filter::exclude_file(path)?);
+        }
+        if crawl.exclude_archived {
+            filters.push(Filter::exclude_archived());
+        }
+        if let Some(age) = crawl.active_within {
+            filters.push(Filter::active_within(age));
+        }
+
+        Ok(Config {
+            max_pages: crawl.max_pages,
+            min_stars: crawl.min_stars.or(settings.minstar).unwrap_or(DEFAULT_MIN_STARS),
+            show_desc: crawl.description,
+            save_snapshot: crawl.snapshot,
+            filters,
+            collapse_forks: crawl.collapse_forks,
+            fetch_metadata: crawl.metadata,
+            ..self
+        })
+    }
+
+    fn with_output(self, settings: &Settings, output: &OutputArgs) -> Result<Self, AppError> {
+        let columns = if output.columns.is_empty() {
+            None
+        } else {
+            Some(output.columns.clone())
+        };
+
+        let output_format = match output.format {
+            _ if output.table => OutputFormat::Table,
+            Some(format) => format,
+            None if output.template.is_some() => OutputFormat::Text,
+            None => settings.format.unwrap_or(DEFAULT_FORMAT),
+        };
+
+        if output.template.is_some() && output_format != OutputFormat::Text {
+            return Err(AppError::Other(
+                "--template can only be used with text output".to_string()
+            ));
+        }
+        if columns.is_some() && output_format == OutputFormat::Json {
+            return Err(AppError::Other(
+                "--columns can only be used with table or text output".to_string()
+            ));
+        }
+
+        if output.merge && self.type_selection != TypeSelection::Both {
+            return Err(AppError::Other("--merge can only be used with --type both".to_string()));
+        }
+
+        Ok(Config {
             output_format,
+            columns,
+            template: output.template.clone(),
+            merge_types: output.merge,
+            ..self
+        })
+    }
+
+    /// Whether the crawl reads the page of every collected dependent, for
+    /// `--metadata` or a filter on metadata fields.
+    pub fn needs_metadata(&self) -> bool {
+        self.fetch_metadata || self.filters.iter().any(Filter::needs_metadata)
+    }
+
+    /// How long cached responses stay valid, or `None` when caching is disabled.
+    pub fn cache_ttl(&self) -> Option<Duration> {
+        self.use_cache.then(|| Duration::from_secs(self.cache_ttl_hours * 3600))
+    }
+
+    /// Where pages that could not be understood are saved for inspection.
+    pub fn debug_dir(&self) -> PathBuf {
+        self.debug_dir.clone().unwrap_or_else(|| get_data_dir().join("debug"))
+    }
+
+    pub fn client_options(&self) -> Result<ClientOptions, AppError> {
+        let token = match &self.token_command {
+            Some(command) => Some(run_token_command(command)?),
+            None => None,
+        };
+        Ok(ClientOptions {
+            token,
+            fixtures: self.fixtures.clone(),
+            ..self.network.clone()
         })
     }
 
+    /// The REST API root: api.github.com for github.com, `/api/v3` on GitHub Enterprise.
+    pub fn api_url(&self) -> String {
+        if self.base_url == GITHUB_BASE_URL {
+            GITHUB_API_URL.to_string()
+        } else {
+            format!("{}/api/v3", self.base_url)
+        }
+    }
+
+    pub fn dependents_url(&self) -> String {
+        format!("{}/{}/{}/network/dependents", self.base_url, self.owner, self.repo)
+    }
+
+    /// The first dependents page for the configured type and package.
+    pub fn first_page_url(&self) -> String {
+        match &self.package_id {
+            Some(package_id) => format!(
+                "{}?dependent_type={}&package_id={}", self.dependents_url(), self.dependent_type(), package_id
+            ),
+            None => format!("{}?dependent_type={}", self.dependents_url(), self.dependent_type()),
+        }
+    }
+
+    /// Names the results of this configuration when several are shown, e.g.
+    /// "packages" or "near-sdk repositories".
+    pub fn section_name(&self) -> String {
+        let type_name = match self.dependent_kind() {
+            DependentType::Repository => "repositories",
+            DependentType::Package => "packages",
+        };
+        let show_type = self.type_selection == TypeSelection::Both && !self.merge_types;
+        match &self.package {
+            Some(package) if show_type => format!("{} {}", package, type_name),
+            Some(package) => package.clone(),
+            None => type_name.to_string(),
+        }
+    }
+
+    /// The snapshot directory name for the crawled type and package.
+    pub fn snapshot_kind(&self) -> String {
+        let type_name = self.dependent_kind().name();
+        match &self.package {
+            Some(package) => format!("{}-{}", type_name, package.replace(['/', '\\'], "_")),
+            None => type_name.to_string(),
+        }
+    }
+
+    pub fn dependent_kind(&self) -> DependentType {
+        if self.is_package { DependentType::Package } else { DependentType::Repository }
+    }
+
     pub fn dependent_type(&self) -> &'static str {
-        if self.is_package { "PACKAGE" } else { "REPOSITORY" }
+        self.dependent_kind().query_value()
+    }
+
+    /// Describes the crawled dependents, e.g. "repository" or "repository and package".
+    pub fn type_label(&self) -> &'static str {
+        match self.type_selection {
+            TypeSelection::Both => "repository and package",
+            _ => self.dependent_kind().name(),
+        }
     }
 }
 
@@ -58,16 +442,16 @@ fn parse_repo_url(repo_url: &str) -> Result<(String, String), AppError> {
         if parts.len() >= 5 {
             Ok((parts[parts.len() - 2].to_string(), parts[parts.len() - 1].to_string()))
         } else {
-            Err(AppError::Other("Invalid GitHub URL format. Expected: https://github.com/owner/repo".to_string()))
+            Err(AppError::InvalidRepoSpec(repo_url.to_string()))
         }
     } else if repo_url.contains('/') {
         let parts: Vec<&str> = repo_url.split('/').collect();
-        if parts.len() == 2 {
+        if parts.len() == 2 && parts.iter().all(|part| !part.is_empty()) {
             Ok((parts[0].to_string(), parts[1].to_string()))
         } else {
-            Err(AppError::Other("Invalid format. Expected: owner/repo or https://github.com/owner/repo".to_string()))
+            Err(AppError::InvalidRepoSpec(repo_url.to_string()))
         }
     } else {
-        Err(AppError::Other("Invalid format. Expected: owner/repo or https://github.com/owner/repo".to_string()))
+        Err(AppError::InvalidRepoSpec(repo_url.to_string()))
     }
 } 
\ No newline at end of file
@@ -1,10 +1,96 @@
+use std::time::{Duration, SystemTime};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependentType {
+    #[default]
+    Repository,
+    Package,
+}
+
+impl DependentType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DependentType::Repository => "repository",
+            DependentType::Package => "package",
+        }
+    }
+
+    /// The `dependent_type` query value of the dependents page.
+    pub fn query_value(&self) -> &'static str {
+        match self {
+            DependentType::Repository => "REPOSITORY",
+            DependentType::Package => "PACKAGE",
+        }
+    }
+}
+
+/// Which dependents lists to crawl.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TypeSelection {
+    #[default]
+    Repositories,
+    Packages,
+    /// Repositories and packages in one run
+    Both,
+}
+
+impl TypeSelection {
+    pub fn types(&self) -> Vec<DependentType> {
+        match self {
+            TypeSelection::Repositories => vec![DependentType::Repository],
+            TypeSelection::Packages => vec![DependentType::Package],
+            TypeSelection::Both => vec![DependentType::Repository, DependentType::Package],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Dependent {
     pub repo: String,
     pub stars: String,
+    pub forks: Option<String>,
     pub description: Option<String>,
+    #[serde(rename = "type", default)]
+    pub dependent_type: DependentType,
+    /// The repository this one was forked or mirrored from, when checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fork_of: Option<String>,
+    /// Forks of this project collapsed into this entry
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub collapsed_forks: usize,
+    /// Details from the repository page, when it was read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<RepoMetadata>,
+}
+
+/// Details shown on a repository's page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoMetadata {
+    #[serde(default)]
+    pub topics: Vec<String>,
+    pub license: Option<String>,
+    /// Primary language
+    pub language: Option<String>,
+    #[serde(default)]
+    pub archived: bool,
+    pub homepage: Option<String>,
+    /// RFC 3339 date of the latest commit on the default branch
+    pub last_commit: Option<String>,
+}
+
+impl RepoMetadata {
+    /// Time since the latest commit, if its date is known.
+    pub fn commit_age(&self, now: SystemTime) -> Option<Duration> {
+        let last_commit = humantime::parse_rfc3339_weak(self.last_commit.as_deref()?).ok()?;
+        Some(now.duration_since(last_commit).unwrap_or_default())
+    }
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
 }
 
 pub fn convert_stars_to_number(stars_text: &str) -> f64 {
@@ -1,12 +1,66 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
 #[derive(Debug)]
 pub enum AppError {
     Network(reqwest::Error),
     Io(std::io::Error),
     Json(serde_json::Error),
+    /// GitHub kept answering 429 Too Many Requests
+    RateLimited {
+        /// The wait GitHub asked for in its `Retry-After` header
+        retry_after: Option<Duration>,
+    },
+    /// An unsuccessful response other than 404 and 429
+    HttpStatus { code: u16, url: String },
+    /// The repository, organization or page does not exist
+    NotFound { url: String },
+    /// A repository that is neither `owner/repo` nor a GitHub URL
+    InvalidRepoSpec(String),
+    /// A response that does not have the expected shape
+    ParseFailure { url: String, reason: String },
+    /// A cached response that could not be read. Crawls warn and fetch the
+    /// page again, so it never ends a run on its own.
+    CacheCorrupt { path: PathBuf, source: Box<AppError> },
     Other(String),
 }
 
-impl std::error::Error for AppError {}
+/// Process exit statuses, so scripts can tell failures apart. Usage errors
+/// exit with 2, like the parse errors reported by clap.
+pub mod exit_code {
+    pub const FAILURE: i32 = 1;
+    pub const USAGE: i32 = 2;
+    pub const NOT_FOUND: i32 = 3;
+    pub const RATE_LIMITED: i32 = 4;
+    pub const NETWORK: i32 = 5;
+    pub const PARSE_FAILURE: i32 = 6;
+}
+
+impl AppError {
+    /// The status the process exits with when this error ends the run.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::NotFound { .. } => exit_code::NOT_FOUND,
+            AppError::RateLimited { .. } => exit_code::RATE_LIMITED,
+            AppError::Network(_) | AppError::HttpStatus { .. } => exit_code::NETWORK,
+            AppError::ParseFailure { .. } => exit_code::PARSE_FAILURE,
+            AppError::InvalidRepoSpec(_) => exit_code::USAGE,
+            AppError::Io(_) | AppError::Json(_) | AppError::CacheCorrupt { .. } | AppError::Other(_) => exit_code::FAILURE,
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Network(e) => Some(e),
+            AppError::Io(e) => Some(e),
+            AppError::Json(e) => Some(e),
+            AppError::CacheCorrupt { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -14,6 +68,19 @@ impl std::fmt::Display for AppError {
             AppError::Network(e) => write!(f, "Network error: {}", e),
             AppError::Io(e) => write!(f, "I/O error: {}", e),
             AppError::Json(e) => write!(f, "JSON error: {}", e),
+            AppError::RateLimited { retry_after: Some(wait) } => write!(
+                f, "Rate limited by GitHub; retry after {}", humantime::format_duration(*wait)
+            ),
+            AppError::RateLimited { retry_after: None } => write!(f, "Rate limited by GitHub"),
+            AppError::HttpStatus { code, url } => write!(f, "HTTP error {} for {}", code, url),
+            AppError::NotFound { url } => write!(f, "Not found: {}", url),
+            AppError::InvalidRepoSpec(spec) => write!(
+                f, "Invalid repository '{}'. Expected: owner/repo or https://github.com/owner/repo", spec
+            ),
+            AppError::ParseFailure { url, reason } => write!(f, "Could not parse {}: {}", url, reason),
+            AppError::CacheCorrupt { path, source } => write!(
+                f, "Corrupt cache entry {}: {}", path.display(), source
+            ),
             AppError::Other(s) => write!(f, "Error: {}", s),
         }
     }
@@ -41,4 +108,4 @@ impl From<String> for AppError {
     fn from(err: String) -> Self {
         AppError::Other(err)
     }
-} 
\ No newline at end of file
+}
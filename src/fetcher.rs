@@ -0,0 +1,109 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+use futures::future::{BoxFuture, FutureExt};
+use reqwest::{header, Client};
+
+use crate::error::AppError;
+
+/// A response to a GET request, whatever its status.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchResponse {
+    pub status: u16,
+    /// The wait a 429 response asks for in its `Retry-After` header
+    pub retry_after: Option<Duration>,
+    pub body: String,
+}
+
+impl FetchResponse {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        FetchResponse {
+            status,
+            retry_after: None,
+            body: body.into(),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Sends the GET requests of a crawl. Unsuccessful statuses are responses;
+/// errors are failures to get any response, which are retried.
+///
+/// Implementations can wrap another fetcher, e.g. to record or rewrite
+/// responses, and are plugged in with [`crate::github::Crawler::with_fetcher`].
+pub trait Fetcher: Send + Sync {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<FetchResponse, AppError>>;
+}
+
+impl Fetcher for Client {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<FetchResponse, AppError>> {
+        async move {
+            let response = Client::get(self, url).send().await?;
+            let retry_after = response.headers().get(header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .map(Duration::from_secs);
+            Ok(FetchResponse {
+                status: response.status().as_u16(),
+                retry_after,
+                body: response.text().await?,
+            })
+        }.boxed()
+    }
+}
+
+/// Answers from responses given in advance, e.g. in tests. Responses for a
+/// URL are returned in order and the last one is repeated; URLs without any
+/// get a 404.
+#[derive(Debug, Default)]
+pub struct MemoryFetcher {
+    responses: Mutex<HashMap<String, VecDeque<Result<FetchResponse, String>>>>,
+    requests: Mutex<Vec<String>>,
+}
+
+impl MemoryFetcher {
+    pub fn new() -> Self {
+        MemoryFetcher::default()
+    }
+
+    /// Adds a response for `url`.
+    pub fn respond(&self, url: &str, response: FetchResponse) -> &Self {
+        self.push(url, Ok(response))
+    }
+
+    /// Adds a failure to get a response for `url`.
+    pub fn fail(&self, url: &str, message: &str) -> &Self {
+        self.push(url, Err(message.to_string()))
+    }
+
+    fn push(&self, url: &str, response: Result<FetchResponse, String>) -> &Self {
+        self.responses.lock().unwrap().entry(url.to_string()).or_default().push_back(response);
+        self
+    }
+
+    /// The URLs requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Fetcher for MemoryFetcher {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<FetchResponse, AppError>> {
+        self.requests.lock().unwrap().push(url.to_string());
+        let mut responses = self.responses.lock().unwrap();
+        let response = match responses.get_mut(url) {
+            Some(queue) if queue.len() > 1 => queue.pop_front(),
+            Some(queue) => queue.front().cloned(),
+            None => None,
+        };
+        let result = match response {
+            Some(Ok(response)) => Ok(response),
+            Some(Err(message)) => Err(AppError::Other(message)),
+            None => Ok(FetchResponse::new(404, "")),
+        };
+        futures::future::ready(result).boxed()
+    }
+}
@@ -0,0 +1,493 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use regex::{Regex, RegexBuilder};
+
+use crate::dependent::{Dependent, convert_stars_to_number};
+use crate::error::AppError;
+
+/// A predicate on dependents, parsed from expressions such as
+/// `stars >= 100 && !owner in [our-org] && name !~ /fork|mirror/`.
+///
+/// Fields are `stars`, `forks`, `repo` (`owner/name`), `owner`, `name`,
+/// `description` and `type`, and from the repository page `topics`,
+/// `license`, `language`, `homepage`, `archived` and `commit_age` (days since
+/// the last commit). Comparisons are `==`, `!=`, `<`, `<=`, `>`, `>=`, `~`
+/// and `!~` (regex match), and `in [a, b]`; `topics` matches when any topic
+/// does and `archived` alone means `archived == true`. Terms combine with
+/// `&&`, `||`, `!` and parentheses. String comparisons ignore case.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Field, CompareOp, f64),
+    Equals(Field, String),
+    In(Field, Vec<String>),
+    Matches(Field, Regex),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Stars,
+    Forks,
+    Repo,
+    Owner,
+    Name,
+    Description,
+    Type,
+    Topics,
+    License,
+    Language,
+    Homepage,
+    Archived,
+    CommitAge,
+}
+
+const FIELD_NAMES: &str = "stars, forks, repo, owner, name, description, type, topics, license, language, homepage, archived, commit_age";
+const SECS_PER_DAY: f64 = 86_400.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Regex(String, bool),
+    Op(&'static str),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Field> {
+        match name {
+            "stars" => Some(Field::Stars),
+            "forks" => Some(Field::Forks),
+            "repo" => Some(Field::Repo),
+            "owner" => Some(Field::Owner),
+            "name" => Some(Field::Name),
+            "description" => Some(Field::Description),
+            "type" => Some(Field::Type),
+            "topics" | "topic" => Some(Field::Topics),
+            "license" => Some(Field::License),
+            "language" => Some(Field::Language),
+            "homepage" => Some(Field::Homepage),
+            "archived" => Some(Field::Archived),
+            "commit_age" => Some(Field::CommitAge),
+            _ => None,
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, Field::Stars | Field::Forks | Field::CommitAge)
+    }
+
+    /// Whether the field comes from the repository page.
+    fn is_metadata(&self) -> bool {
+        matches!(self, Field::Topics | Field::License | Field::Language | Field::Homepage | Field::Archived | Field::CommitAge)
+    }
+
+    /// The field as a number. An unknown commit age is NaN, so every
+    /// comparison but `!=` fails.
+    fn number(&self, dep: &Dependent) -> f64 {
+        match self {
+            Field::Stars => convert_stars_to_number(&dep.stars),
+            Field::Forks => dep.forks.as_deref().map(convert_stars_to_number).unwrap_or(0.0),
+            Field::CommitAge => dep.metadata.as_ref()
+                .and_then(|metadata| metadata.commit_age(SystemTime::now()))
+                .map_or(f64::NAN, |age| age.as_secs_f64() / SECS_PER_DAY),
+            _ => 0.0,
+        }
+    }
+
+    /// The field's values as text; `topics` has one per topic.
+    fn texts<'a>(&self, dep: &'a Dependent) -> Vec<&'a str> {
+        let metadata = dep.metadata.as_ref();
+        let text = match self {
+            Field::Stars => &dep.stars,
+            Field::Forks => dep.forks.as_deref().unwrap_or(""),
+            Field::Repo => &dep.repo,
+            Field::Owner => dep.repo.split_once('/').map_or(dep.repo.as_str(), |(owner, _)| owner),
+            Field::Name => dep.repo.split_once('/').map_or("", |(_, name)| name),
+            Field::Description => dep.description.as_deref().unwrap_or(""),
+            Field::Type => dep.dependent_type.name(),
+            Field::Topics => {
+                return metadata.map(|metadata| metadata.topics.iter().map(String::as_str).collect())
+                    .unwrap_or_default();
+            },
+            Field::License => metadata.and_then(|metadata| metadata.license.as_deref()).unwrap_or(""),
+            Field::Language => metadata.and_then(|metadata| metadata.language.as_deref()).unwrap_or(""),
+            Field::Homepage => metadata.and_then(|metadata| metadata.homepage.as_deref()).unwrap_or(""),
+            Field::Archived => if metadata.is_some_and(|metadata| metadata.archived) { "true" } else { "false" },
+            Field::CommitAge => "",
+        };
+        vec![text]
+    }
+}
+
+impl Expr {
+    fn eval(&self, dep: &Dependent) -> bool {
+        match self {
+            Expr::And(left, right) => left.eval(dep) && right.eval(dep),
+            Expr::Or(left, right) => left.eval(dep) || right.eval(dep),
+            Expr::Not(inner) => !inner.eval(dep),
+            Expr::Compare(field, op, value) => {
+                let actual = field.number(dep);
+                match op {
+                    CompareOp::Eq => actual == *value,
+                    CompareOp::Ne => actual != *value,
+                    CompareOp::Lt => actual < *value,
+                    CompareOp::Le => actual <= *value,
+                    CompareOp::Gt => actual > *value,
+                    CompareOp::Ge => actual >= *value,
+                }
+            },
+            Expr::Equals(field, value) => field.texts(dep).iter().any(|text| text.eq_ignore_ascii_case(value)),
+            Expr::In(field, values) => {
+                if field.is_numeric() {
+                    values.iter().any(|value| convert_stars_to_number(value) == field.number(dep))
+                } else {
+                    let texts = field.texts(dep);
+                    values.iter().any(|value| texts.iter().any(|text| text.eq_ignore_ascii_case(value)))
+                }
+            },
+            Expr::Matches(field, regex) => field.texts(dep).iter().any(|text| regex.is_match(text)),
+        }
+    }
+
+    fn uses_metadata(&self) -> bool {
+        match self {
+            Expr::And(left, right) | Expr::Or(left, right) => left.uses_metadata() || right.uses_metadata(),
+            Expr::Not(inner) => inner.uses_metadata(),
+            Expr::Compare(field, _, _) | Expr::Equals(field, _) | Expr::In(field, _) | Expr::Matches(field, _) => {
+                field.is_metadata()
+            },
+        }
+    }
+}
+
+impl Filter {
+    pub fn matches(&self, dep: &Dependent) -> bool {
+        self.expr.eval(dep)
+    }
+
+    /// Whether the filter reads fields from the repository page.
+    pub fn needs_metadata(&self) -> bool {
+        self.expr.uses_metadata()
+    }
+
+    /// Drops dependents owned by any of `owners`.
+    pub fn exclude_owners(owners: &[String]) -> Filter {
+        Filter {
+            expr: Expr::Not(Box::new(Expr::In(Field::Owner, owners.to_vec()))),
+        }
+    }
+
+    /// Keeps dependents whose `owner/name` matches `regex`.
+    pub fn include_regex(regex: Regex) -> Filter {
+        Filter {
+            expr: Expr::Matches(Field::Repo, regex),
+        }
+    }
+
+    /// Drops the repositories (`owner/name`) and owners listed in a file, one
+    /// per line. Blank lines and lines starting with `#` are skipped.
+    pub fn exclude_file(path: &Path) -> Result<Filter, AppError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| AppError::Other(format!("Could not read {}: {}", path.display(), e)))?;
+        let (repos, owners): (Vec<String>, Vec<String>) = content.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.trim_matches('/').to_string())
+            .partition(|line| line.contains('/'));
+
+        let excluded = Expr::Or(
+            Box::new(Expr::In(Field::Repo, repos)),
+            Box::new(Expr::In(Field::Owner, owners)),
+        );
+        Ok(Filter {
+            expr: Expr::Not(Box::new(excluded)),
+        })
+    }
+
+    /// Drops archived repositories.
+    pub fn exclude_archived() -> Filter {
+        Filter {
+            expr: Expr::Not(Box::new(Expr::Equals(Field::Archived, "true".to_string()))),
+        }
+    }
+
+    /// Keeps repositories with a commit within `age`.
+    pub fn active_within(age: Duration) -> Filter {
+        Filter {
+            expr: Expr::Compare(Field::CommitAge, CompareOp::Le, age.as_secs_f64() / SECS_PER_DAY),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(filter_error(format!("unexpected {}", describe(token))));
+        }
+        Ok(Filter { expr })
+    }
+}
+
+fn filter_error(message: String) -> AppError {
+    AppError::Other(format!("Invalid filter: {}", message))
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("'{}'", word),
+        Token::Str(value) => format!("\"{}\"", value),
+        Token::Regex(pattern, _) => format!("/{}/", pattern),
+        Token::Op(op) => format!("'{}'", op),
+        Token::LParen => "'('".to_string(),
+        Token::RParen => "')'".to_string(),
+        Token::LBracket => "'['".to_string(),
+        Token::RBracket => "']'".to_string(),
+        Token::Comma => "','".to_string(),
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '/')
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, AppError> {
+    const OPERATORS: [&str; 12] = ["&&", "||", "==", "!=", "!~", ">=", "<=", ">", "<", "~", "!", "="];
+
+    let mut tokens = Vec::new();
+    let mut rest = input.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let consumed = match c {
+            '(' => { tokens.push(Token::LParen); 1 },
+            ')' => { tokens.push(Token::RParen); 1 },
+            '[' => { tokens.push(Token::LBracket); 1 },
+            ']' => { tokens.push(Token::RBracket); 1 },
+            ',' => { tokens.push(Token::Comma); 1 },
+            '"' | '\'' => {
+                let end = rest[1..].find(c)
+                    .ok_or_else(|| filter_error(format!("unclosed string {}", rest)))?;
+                tokens.push(Token::Str(rest[1..=end].to_string()));
+                end + 2
+            },
+            '/' => {
+                // A regex runs to the next unescaped '/', optionally followed by 'i'
+                let mut end = None;
+                let mut escaped = false;
+                for (idx, ch) in rest.char_indices().skip(1) {
+                    match ch {
+                        '\\' if !escaped => escaped = true,
+                        '/' if !escaped => { end = Some(idx); break; },
+                        _ => escaped = false,
+                    }
+                }
+                let end = end.ok_or_else(|| filter_error(format!("unclosed regex {}", rest)))?;
+                let pattern = rest[1..end].replace("\\/", "/");
+                let ignore_case = rest[end + 1..].starts_with('i')
+                    && !rest[end + 2..].starts_with(is_word_char);
+                tokens.push(Token::Regex(pattern, ignore_case));
+                end + 1 + usize::from(ignore_case)
+            },
+            c if is_word_char(c) => {
+                let end = rest.find(|ch: char| !is_word_char(ch)).unwrap_or(rest.len());
+                tokens.push(Token::Word(rest[..end].to_string()));
+                end
+            },
+            _ => {
+                let op = OPERATORS.iter().find(|op| rest.starts_with(**op))
+                    .ok_or_else(|| filter_error(format!("unexpected character '{}'", c)))?;
+                tokens.push(Token::Op(if *op == "=" { "==" } else { op }));
+                op.len()
+            },
+        };
+        rest = rest[consumed..].trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), AppError> {
+        match self.next() {
+            Some(found) if found == token => Ok(()),
+            Some(found) => Err(filter_error(format!("expected {}, found {}", describe(&token), describe(&found)))),
+            None => Err(filter_error(format!("expected {} at end of filter", describe(&token)))),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, AppError> {
+        let mut expr = self.parse_and()?;
+        while self.eat(&Token::Op("||")) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, AppError> {
+        let mut expr = self.parse_unary()?;
+        while self.eat(&Token::Op("&&")) {
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, AppError> {
+        if self.eat(&Token::Op("!")) {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.eat(&Token::LParen) {
+            let expr = self.parse_or()?;
+            self.expect(Token::RParen)?;
+            return Ok(expr);
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, AppError> {
+        let field = match self.next() {
+            Some(Token::Word(name)) => Field::parse(&name).ok_or_else(|| filter_error(format!(
+                "unknown field '{}'. Expected one of: {}", name, FIELD_NAMES
+            )))?,
+            Some(token) => return Err(filter_error(format!("expected a field, found {}", describe(&token)))),
+            None => return Err(filter_error("expected a field at end of filter".to_string())),
+        };
+
+        let is_comparison = match self.peek() {
+            Some(Token::Op(op)) => !matches!(*op, "&&" | "||" | "!"),
+            Some(Token::Word(word)) => word == "in",
+            _ => false,
+        };
+        if field == Field::Archived && !is_comparison {
+            return Ok(Expr::Equals(field, "true".to_string()));
+        }
+
+        match self.next() {
+            Some(Token::Word(word)) if word == "in" => {
+                self.expect(Token::LBracket)?;
+                let mut values = Vec::new();
+                if !self.eat(&Token::RBracket) {
+                    loop {
+                        values.push(self.parse_value()?);
+                        if self.eat(&Token::RBracket) {
+                            break;
+                        }
+                        self.expect(Token::Comma)?;
+                    }
+                }
+                Ok(Expr::In(field, values))
+            },
+            Some(Token::Op(op @ ("~" | "!~"))) => {
+                if field.is_numeric() {
+                    return Err(filter_error(format!("'{}' cannot be used with a number field", op)));
+                }
+                let regex = match self.next() {
+                    Some(Token::Regex(pattern, ignore_case)) => build_regex(&pattern, ignore_case)?,
+                    Some(Token::Str(pattern)) | Some(Token::Word(pattern)) => build_regex(&pattern, false)?,
+                    Some(token) => return Err(filter_error(format!("expected a regex, found {}", describe(&token)))),
+                    None => return Err(filter_error("expected a regex at end of filter".to_string())),
+                };
+                let expr = Expr::Matches(field, regex);
+                Ok(if op == "!~" { Expr::Not(Box::new(expr)) } else { expr })
+            },
+            Some(Token::Op(op @ ("==" | "!=" | "<" | "<=" | ">" | ">="))) => {
+                let value = self.parse_value()?;
+                if field.is_numeric() {
+                    let number = parse_number(&value)?;
+                    let op = match op {
+                        "==" => CompareOp::Eq,
+                        "!=" => CompareOp::Ne,
+                        "<" => CompareOp::Lt,
+                        "<=" => CompareOp::Le,
+                        ">" => CompareOp::Gt,
+                        _ => CompareOp::Ge,
+                    };
+                    Ok(Expr::Compare(field, op, number))
+                } else {
+                    match op {
+                        "==" => Ok(Expr::Equals(field, value)),
+                        "!=" => Ok(Expr::Not(Box::new(Expr::Equals(field, value)))),
+                        _ => Err(filter_error(format!("'{}' can only be used with stars, forks and commit_age", op))),
+                    }
+                }
+            },
+            Some(token) => Err(filter_error(format!("expected a comparison, found {}", describe(&token)))),
+            None => Err(filter_error("expected a comparison at end of filter".to_string())),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<String, AppError> {
+        match self.next() {
+            Some(Token::Word(value)) | Some(Token::Str(value)) => Ok(value),
+            Some(token) => Err(filter_error(format!("expected a value, found {}", describe(&token)))),
+            None => Err(filter_error("expected a value at end of filter".to_string())),
+        }
+    }
+}
+
+fn parse_number(value: &str) -> Result<f64, AppError> {
+    let lower = value.to_lowercase();
+    let (digits, scale) = match lower.strip_suffix('k') {
+        Some(digits) => (digits, 1000.0),
+        None => (lower.as_str(), 1.0),
+    };
+    digits.replace(',', "").parse::<f64>()
+        .map(|number| number * scale)
+        .map_err(|_| filter_error(format!("'{}' is not a number", value)))
+}
+
+fn build_regex(pattern: &str, ignore_case: bool) -> Result<Regex, AppError> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| filter_error(format!("invalid regex /{}/: {}", pattern, e)))
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::future::{BoxFuture, FutureExt};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::fetcher::{FetchResponse, Fetcher};
+
+/// Whether to record responses to, or replay them from, a fixture directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixtureMode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// The file listing the recorded responses of a fixture directory.
+pub const INDEX_FILE: &str = "index.json";
+
+/// A recorded response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fixture {
+    pub status: u16,
+    /// The body, relative to the fixture directory
+    pub file: String,
+    /// Seconds of the `Retry-After` header of a 429 response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+}
+
+/// `index.json`: recorded responses keyed by path and query, so they are
+/// replayed whatever the base URL.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FixtureIndex {
+    pub responses: BTreeMap<String, Fixture>,
+}
+
+/// The part of `url` fixtures are keyed by, e.g. `/owner/repo?tab=readme`.
+pub fn fixture_key(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(url) => match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Writes every response it is given to a fixture directory, updating the
+/// index after each so an interrupted crawl still leaves usable fixtures.
+pub struct FixtureRecorder {
+    dir: PathBuf,
+    index: Mutex<FixtureIndex>,
+}
+
+impl FixtureRecorder {
+    /// Records into `dir`, keeping the responses of earlier recordings there.
+    pub fn create(dir: &Path) -> Result<FixtureRecorder, AppError> {
+        fs::create_dir_all(dir)?;
+        let index = if dir.join(INDEX_FILE).exists() {
+            Fixtures::load(dir)?.index
+        } else {
+            FixtureIndex::default()
+        };
+        Ok(FixtureRecorder {
+            dir: dir.to_path_buf(),
+            index: Mutex::new(index),
+        })
+    }
+
+    /// Records a response; a later response for the same URL replaces it.
+    pub fn record(&self, url: &str, response: &FetchResponse) -> Result<(), AppError> {
+        let mut index = self.index.lock().unwrap();
+        let key = fixture_key(url);
+        let file = match index.responses.get(&key) {
+            Some(existing) => existing.file.clone(),
+            None => (index.responses.len() + 1..)
+                .map(|n| format!("{:04}.html", n))
+                .find(|file| !self.dir.join(file).exists())
+                .expect("file numbers are unbounded"),
+        };
+        fs::write(self.dir.join(&file), &response.body)?;
+        index.responses.insert(key, Fixture {
+            status: response.status,
+            file,
+            retry_after: response.retry_after.map(|wait| wait.as_secs()),
+        });
+        fs::write(self.dir.join(INDEX_FILE), serde_json::to_string_pretty(&*index)?)?;
+        Ok(())
+    }
+}
+
+/// Responses loaded from a fixture directory.
+#[derive(Debug)]
+pub struct Fixtures {
+    dir: PathBuf,
+    index: FixtureIndex,
+}
+
+impl Fixtures {
+    pub fn load(dir: &Path) -> Result<Fixtures, AppError> {
+        let index_path = dir.join(INDEX_FILE);
+        let content = fs::read_to_string(&index_path)
+            .map_err(|e| AppError::Other(format!("Could not read {}: {}", index_path.display(), e)))?;
+        let index = serde_json::from_str(&content).map_err(|e| AppError::ParseFailure {
+            url: index_path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        Ok(Fixtures { dir: dir.to_path_buf(), index })
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.responses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.responses.is_empty()
+    }
+
+    /// The recorded response for `key`, see [`fixture_key`].
+    pub fn response(&self, key: &str) -> Option<Result<FetchResponse, AppError>> {
+        let fixture = self.index.responses.get(key)?;
+        Some(fs::read_to_string(self.dir.join(&fixture.file))
+            .map(|body| FetchResponse {
+                retry_after: fixture.retry_after.map(Duration::from_secs),
+                ..FetchResponse::new(fixture.status, body)
+            })
+            .map_err(AppError::from))
+    }
+}
+
+/// Answers every request from recorded responses, without a network.
+/// Requests without a fixture get a 404.
+pub struct ReplayFetcher {
+    fixtures: Fixtures,
+}
+
+impl ReplayFetcher {
+    pub fn new(fixtures: Fixtures) -> Self {
+        ReplayFetcher { fixtures }
+    }
+}
+
+impl Fetcher for ReplayFetcher {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<FetchResponse, AppError>> {
+        let key = fixture_key(url);
+        let response = match self.fixtures.response(&key) {
+            Some(response) => response,
+            None => {
+                eprintln!("Warning: No fixture for {}", key);
+                Ok(FetchResponse::new(404, ""))
+            },
+        };
+        futures::future::ready(response).boxed()
+    }
+}
+
+/// Passes requests on to another fetcher and records its responses.
+pub struct RecordingFetcher {
+    inner: Arc<dyn Fetcher>,
+    recorder: FixtureRecorder,
+}
+
+impl RecordingFetcher {
+    pub fn new(inner: Arc<dyn Fetcher>, dir: &Path) -> Result<Self, AppError> {
+        Ok(RecordingFetcher {
+            inner,
+            recorder: FixtureRecorder::create(dir)?,
+        })
+    }
+}
+
+impl Fetcher for RecordingFetcher {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<FetchResponse, AppError>> {
+        async move {
+            let response = self.inner.get(url).await?;
+            if let Err(e) = self.recorder.record(url, &response) {
+                eprintln!("Warning: Could not record {}: {}", url, e);
+            }
+            Ok(response)
+        }.boxed()
+    }
+}
+
+/// The fetcher for `mode`: one replaying recorded responses, or `live` with
+/// its responses recorded.
+pub fn fixture_fetcher(mode: &FixtureMode, live: Arc<dyn Fetcher>) -> Result<Arc<dyn Fetcher>, AppError> {
+    Ok(match mode {
+        FixtureMode::Record(dir) => Arc::new(RecordingFetcher::new(live, dir)?),
+        FixtureMode::Replay(dir) => Arc::new(ReplayFetcher::new(Fixtures::load(dir)?)),
+    })
+}
@@ -1,269 +1,798 @@
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use futures::stream::{self, StreamExt};
-use indicatif::{ProgressBar, ProgressStyle};
-use reqwest::Client;
-use scraper::{Html, Selector};
-use tokio::sync::Semaphore;
-use tokio::time::sleep;
-
-use crate::cache::{get_cache_path, is_cache_valid, read_from_cache, write_to_cache};
-use crate::client::{create_client, fetch_with_retry};
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{get_cache_path, is_cache_valid_for, read_from_cache, write_to_cache};
+use crate::client::{create_client_with, fetch_with_retry, ClientOptions, RateLimiter};
 use crate::config::Config;
-use crate::dependent::{Dependent, convert_stars_to_number};
+use crate::dependent::{Dependent, RepoMetadata, convert_stars_to_number};
 use crate::error::AppError;
+use crate::fetcher::Fetcher;
+use crate::fixtures::fixture_fetcher;
+use crate::metrics::{metrics, CacheResult};
+use crate::progress::Progress;
+use crate::selectors::selectors;
 
-const GITHUB_BASE_URL: &str = "https://github.com";
+pub const GITHUB_BASE_URL: &str = "https://github.com";
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+const ORG_REPOS_PER_PAGE: usize = 100;
 const REPOS_PER_PAGE: usize = 30;
+const PAGE_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_CONCURRENT_REQUESTS: usize = 5;
+
+/// A row of a dependents page: (repo, stars, forks).
+pub type DependentRow = (String, String, Option<String>);
 
-pub async fn cached_fetch(client: &Client, url: &str, use_cache: bool) -> Result<String, AppError> {
+/// Fetches `url`, serving it from the cache when `cache_ttl` is set and the
+/// cached copy is younger than it. `None` disables caching.
+pub async fn cached_fetch(fetcher: &dyn Fetcher, url: &str, cache_ttl: Option<Duration>) -> Result<String, AppError> {
     let cache_path = get_cache_path(url);
     
-    if use_cache && is_cache_valid(&cache_path) {
-        match read_from_cache(&cache_path) {
-            Ok(content) => return Ok(content),
-            Err(e) => println!("Warning: Cache read error: {}", e),
+    if let Some(ttl) = cache_ttl {
+        if is_cache_valid_for(&cache_path, ttl) {
+            match read_from_cache(&cache_path) {
+                Ok(content) => {
+                    metrics().record_cache(CacheResult::Hit);
+                    return Ok(content);
+                },
+                Err(e) => {
+                    eprintln!("Warning: Cache read error: {}", e);
+                    metrics().record_cache(CacheResult::Corrupt);
+                },
+            }
+        } else {
+            metrics().record_cache(CacheResult::Miss);
         }
     }
     
     // Fetch and cache with compression
-    let html = fetch_with_retry(client, url, 3).await?;
+    let html = fetch_with_retry(fetcher, url, 3).await?;
     
-    if use_cache {
+    if cache_ttl.is_some() {
         if let Err(e) = write_to_cache(&cache_path, &html) {
-            println!("Warning: Cache write error: {}", e);
+            eprintln!("Warning: Cache write error: {}", e);
         }
     }
     
     Ok(html)
 }
 
-pub async fn get_max_deps(client: &Client, url: &str, dependent_type: &str, use_cache: bool) -> usize {
-    let full_url = format!("{}?dependent_type={}", url, dependent_type);
+/// Dependents counts from the "N Repositories" and "N Packages" links at
+/// the top of a dependents page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeaderCounts {
+    pub repositories: Option<usize>,
+    pub packages: Option<usize>,
+}
+
+impl HeaderCounts {
+    /// The count for a `dependent_type` query value.
+    pub fn for_type(&self, dependent_type: &str) -> Option<usize> {
+        if dependent_type == "PACKAGE" { self.packages } else { self.repositories }
+    }
+}
+
+pub fn parse_header_counts(html: &str) -> HeaderCounts {
+    let document = Html::parse_document(html);
+    let mut counts = HeaderCounts::default();
     
-    match cached_fetch(client, &full_url, use_cache).await {
-        Ok(html) => {
-            let document = Html::parse_document(&html);
-            let selector = Selector::parse(".table-list-header-toggle .btn-link.selected").unwrap();
-            
-            if let Some(element) = document.select(&selector).next() {
-                let text = element.text().collect::<Vec<_>>().join("");
-                let count_str = text.trim().split_whitespace().next().unwrap_or("0");
-                return count_str.replace(',', "").parse::<usize>().unwrap_or(0);
-            }
-        },
-        Err(e) => {
-            println!("Error getting max deps: {}", e);
+    for element in document.select(&selectors().header_counts) {
+        let text = element.text().collect::<Vec<_>>().join("");
+        let mut words = text.split_whitespace();
+        let count = words.next().and_then(|count| count.replace(',', "").parse::<usize>().ok());
+        match words.last().map(str::to_lowercase) {
+            Some(label) if label.starts_with("repositor") => counts.repositories = count,
+            Some(label) if label.starts_with("package") => counts.packages = count,
+            _ => {}
         }
     }
     
-    0
+    counts
+}
+
+/// Reads the header counts of the dependents page at `page_url`.
+pub async fn fetch_header_counts(
+    fetcher: &dyn Fetcher, 
+    page_url: &str, 
+    cache_ttl: Option<Duration>
+) -> Result<HeaderCounts, AppError> {
+    Ok(parse_header_counts(&cached_fetch(fetcher, page_url, cache_ttl).await?))
 }
 
-pub async fn get_repo_description(client: &Client, repo_path: &str, use_cache: bool) -> Option<String> {
-    let url = format!("{}/{}", GITHUB_BASE_URL, repo_path);
+/// A package listed in the package dropdown of a dependents page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageOption {
+    pub name: String,
+    /// The `package_id` query value selecting the package
+    pub id: String,
+    /// Whether the page shows this package's dependents
+    pub selected: bool,
+}
+
+pub fn parse_packages(html: &str) -> Vec<PackageOption> {
+    let document = Html::parse_document(html);
+    let selectors = selectors();
     
-    match cached_fetch(client, &url, use_cache).await {
-        Ok(html) => {
-            let document = Html::parse_document(&html);
-            let selector = Selector::parse("div.BorderGrid-cell p").unwrap();
-            
-            if let Some(element) = document.select(&selector).next() {
-                let text = element.text().collect::<Vec<_>>().join("");
-                return Some(text.trim().to_string());
-            }
+    document.select(&selectors.package_item)
+        .filter_map(|item| {
+            let href = item.value().attr("href")?;
+            let (_, query) = href.split_once('?')?;
+            let id = query.split('&').find_map(|param| param.strip_prefix("package_id="))?;
+            let name = match item.select(&selectors.package_text).next() {
+                Some(text) => text.text().collect::<String>(),
+                None => item.text().collect::<String>(),
+            };
+            Some(PackageOption {
+                name: name.trim().to_string(),
+                id: id.to_string(),
+                selected: item.value().attr("aria-checked") == Some("true"),
+            })
+        })
+        .collect()
+}
+
+/// What a repository page says about the repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoPage {
+    pub description: Option<String>,
+    /// See [`parse_fork_parent`]
+    pub fork_of: Option<String>,
+    pub metadata: RepoMetadata,
+}
+
+pub fn parse_repo_page(html: &str) -> RepoPage {
+    let document = Html::parse_document(html);
+    let selectors = selectors();
+    
+    let text_of = |element: ElementRef| {
+        let text = element.text().collect::<String>();
+        Some(text.trim().to_string()).filter(|text| !text.is_empty())
+    };
+    // The license and homepage are the text next to their icons
+    let next_to_icon = |selector: &Selector| document.select(selector).next()
+        .and_then(|icon| icon.parent())
+        .and_then(ElementRef::wrap);
+    
+    let description = document.select(&selectors.description).next()
+        .map(|element| element.text().collect::<String>().trim().to_string());
+    let topics = document.select(&selectors.topic)
+        .filter_map(text_of)
+        .collect();
+    let license = next_to_icon(&selectors.license_icon).and_then(text_of);
+    let homepage = next_to_icon(&selectors.homepage_icon)
+        .and_then(|parent| parent.select(&selectors.homepage_link).next())
+        .and_then(|link| link.value().attr("href"))
+        .map(str::to_string);
+    // Older pages mark the language up; newer ones list it first under "Languages"
+    let language = document.select(&selectors.language).next().and_then(text_of)
+        .or_else(|| document.select(&selectors.sidebar_cell)
+            .find(|cell| cell.select(&selectors.sidebar_heading).next()
+                .is_some_and(|heading| heading.text().collect::<String>().trim() == "Languages"))
+            .and_then(|cell| cell.select(&selectors.sidebar_language).next())
+            .and_then(text_of));
+    let archived = document.select(&selectors.archived_notice).any(|element| {
+        let text = element.text().collect::<String>().to_lowercase();
+        text.contains("has been archived") || text.trim() == "public archive"
+    });
+    let last_commit = document.select(&selectors.last_commit).next()
+        .and_then(|element| element.value().attr("datetime"))
+        .map(str::to_string);
+    
+    RepoPage {
+        description,
+        fork_of: parse_fork_parent(html),
+        metadata: RepoMetadata {
+            topics,
+            license,
+            language,
+            archived,
+            homepage,
+            last_commit,
         },
+    }
+}
+
+/// The repository a repository page says it was forked or mirrored from:
+/// `owner/repo` for GitHub forks, the source URL for mirrors.
+pub fn parse_fork_parent(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    
+    document.select(&selectors().fork_link).find_map(|link| {
+        let parent = link.parent().and_then(ElementRef::wrap)?;
+        let text = parent.text().collect::<String>().trim_start().to_lowercase();
+        if !text.starts_with("forked from") && !text.starts_with("mirrored from") {
+            return None;
+        }
+        let href = link.value().attr("href")?.trim_end_matches('/');
+        let parent_repo = if href.starts_with('/') {
+            href.trim_start_matches('/')
+        } else {
+            href
+        };
+        Some(parent_repo.to_lowercase())
+    })
+}
+
+pub async fn get_repo_page(
+    fetcher: &dyn Fetcher, 
+    base_url: &str, 
+    repo_path: &str, 
+    cache_ttl: Option<Duration>
+) -> Option<RepoPage> {
+    let url = format!("{}/{}", base_url, repo_path);
+    
+    match cached_fetch(fetcher, &url, cache_ttl).await {
+        Ok(html) => Some(parse_repo_page(&html)),
         Err(e) => {
-            println!("Error getting description for {}: {}", repo_path, e);
+            eprintln!("Error reading the page of {}: {}", repo_path, e);
+            None
         }
     }
-    
-    None
 }
 
-pub fn parse_page(html: &str) -> (Vec<(String, String)>, Option<String>) {
+pub async fn get_repo_description(
+    fetcher: &dyn Fetcher, 
+    base_url: &str, 
+    repo_path: &str, 
+    cache_ttl: Option<Duration>
+) -> Option<String> {
+    get_repo_page(fetcher, base_url, repo_path, cache_ttl).await?.description
+}
+
+pub fn parse_page(html: &str) -> (Vec<DependentRow>, Option<String>) {
     let document = Html::parse_document(html);
-    let repo_selector = Selector::parse(".flex-items-center").unwrap();
-    let link_selector = Selector::parse("a.text-bold").unwrap();
-    let stars_selector = Selector::parse("div span").unwrap();
-    let next_selector = Selector::parse(".paginate-container a").unwrap();
+    let selectors = selectors();
     
     let mut dependents = Vec::new();
     
-    for element in document.select(&repo_selector) {
-        if let Some(link_element) = element.select(&link_selector).next() {
+    for element in document.select(&selectors.dependent_row) {
+        if let Some(link_element) = element.select(&selectors.dependent_link).next() {
             if let Some(href) = link_element.value().attr("href") {
                 let repo = href.trim_start_matches('/').to_lowercase();
                 
-                if let Some(stars_element) = element.select(&stars_selector).next() {
+                if let Some(stars_element) = element.select(&selectors.stars).next() {
                     let stars = stars_element.text().collect::<String>().trim().to_string();
-                    dependents.push((repo, stars));
+                    // The fork count is the text next to the fork icon
+                    let forks = element.select(&selectors.forks_icon).next()
+                        .and_then(|icon| icon.parent())
+                        .and_then(ElementRef::wrap)
+                        .map(|parent| parent.text().collect::<String>().trim().to_string());
+                    dependents.push((repo, stars, forks));
                 }
             }
         }
     }
     
-    let next_link = document.select(&next_selector).next()
+    let next_link = document.select(&selectors.next_page).next()
         .and_then(|el| el.value().attr("href"))
         .map(|href| href.to_string());
     
     (dependents, next_link)
 }
 
-pub async fn get_top_dependents(
-    config: &Config,
-) -> Result<(Vec<Dependent>, usize, usize, usize), Box<dyn std::error::Error>> {
-    let base_url = format!("{}/{}/{}/network/dependents", 
-        GITHUB_BASE_URL, config.owner, config.repo);
-    let mut page_url = format!("{}?dependent_type={}", base_url, config.dependent_type());
+/// What a page fetched as a dependents page turned out to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageKind {
+    /// A page listing dependents
+    Dependents,
+    /// A dependents page listing none, e.g. of a repository nobody depends on
+    Empty,
+    /// A sign-in form instead of the dependents
+    LoginWall,
+    /// GitHub's abuse detection or secondary rate limit page
+    AbuseDetection,
+    /// GitHub's "Page not found"
+    NotFound,
+    /// Markup the parser does not recognize, e.g. after a GitHub redesign
+    Unrecognized,
+}
+
+impl PageKind {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            PageKind::Dependents => "dependents page",
+            PageKind::Empty => "dependents page without dependents",
+            PageKind::LoginWall => "sign-in page",
+            PageKind::AbuseDetection => "abuse detection page",
+            PageKind::NotFound => "page not found",
+            PageKind::Unrecognized => "unrecognized page",
+        }
+    }
+}
+
+/// Tells a dependents page GitHub has nothing on apart from pages without
+/// any dependents for another reason.
+pub fn classify_page(html: &str) -> PageKind {
+    if !parse_page(html).0.is_empty() {
+        return PageKind::Dependents;
+    }
     
-    let client = create_client()?;
+    let document = Html::parse_document(html);
+    let selectors = selectors();
     
-    let mut all_dependents = Vec::new();
-    let mut page_count = 0;
+    let title = document.select(&selectors.page_title).next()
+        .map(|title| title.text().collect::<String>().to_lowercase())
+        .unwrap_or_default();
+    let text = document.root_element().text().collect::<String>().to_lowercase();
     
-    // Get the maximum number of dependents
-    let max_deps = get_max_deps(&client, &base_url, config.dependent_type(), config.use_cache).await;
-    let pb = if max_deps > 0 {
-        println!("Found {} total dependents", max_deps);
-        let total = std::cmp::min(max_deps, config.max_pages * REPOS_PER_PAGE);
-        let pb = ProgressBar::new(total as u64);
-        pb.set_style(ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:50.cyan/blue}] {percent}% ({pos}/{len}) [{eta}]")
-            .unwrap()
-            .progress_chars("█▉▊▋▌▍▎▏ "));
-        pb
+    if text.contains("abuse detection") || text.contains("secondary rate limit") || title.contains("too many requests") {
+        PageKind::AbuseDetection
+    } else if title.contains("page not found") {
+        PageKind::NotFound
+    } else if document.select(&selectors.login_form).next().is_some() || title.starts_with("sign in") {
+        PageKind::LoginWall
+    } else if document.select(&selectors.blankslate).next().is_some()
+        || text.contains("found any dependents")
+        || header_counts_zero(parse_header_counts(html)) {
+        PageKind::Empty
     } else {
-        ProgressBar::new_spinner()
-    };
+        PageKind::Unrecognized
+    }
+}
+
+/// Whether the header says there are no dependents. Nonzero counts on a
+/// page without dependents mean the rows were not recognized.
+fn header_counts_zero(counts: HeaderCounts) -> bool {
+    counts != HeaderCounts::default() && counts.repositories.unwrap_or(0) == 0 && counts.packages.unwrap_or(0) == 0
+}
+
+/// Saves a page that could not be understood to `dir` and returns its path.
+fn save_debug_page(dir: &Path, config: &Config, page_number: usize, html: &str) -> Option<PathBuf> {
+    let taken_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let path = dir.join(format!("{}_{}_page{}_{}.html", config.owner, config.repo, page_number, taken_at));
+    match fs::create_dir_all(dir).and_then(|_| fs::write(&path, html)) {
+        Ok(()) => Some(path),
+        Err(e) => {
+            eprintln!("Warning: Could not save the page to {}: {}", dir.display(), e);
+            None
+        }
+    }
+}
+
+/// Checks a dependents page without dependents: `Ok` when GitHub lists none,
+/// otherwise an error telling what the page is, after saving it for inspection.
+fn check_empty_page(config: &Config, url: &str, page_number: usize, html: &str) -> Result<(), AppError> {
+    let kind = classify_page(html);
+    if kind == PageKind::Empty {
+        return Ok(());
+    }
+    
+    let saved = save_debug_page(&config.debug_dir(), config, page_number, html)
+        .map(|path| format!(" (page saved to {})", path.display()))
+        .unwrap_or_default();
+    Err(match kind {
+        PageKind::NotFound => AppError::NotFound { url: url.to_string() },
+        PageKind::AbuseDetection => {
+            eprintln!("Warning: GitHub's abuse detection blocked {}{}", url, saved);
+            AppError::RateLimited { retry_after: None }
+        },
+        PageKind::LoginWall => AppError::ParseFailure {
+            url: url.to_string(),
+            reason: format!("GitHub asked to sign in instead of listing dependents{}", saved),
+        },
+        _ => AppError::ParseFailure {
+            url: url.to_string(),
+            reason: format!("no dependents in an unrecognized page; GitHub may have changed its layout{}", saved),
+        },
+    })
+}
 
-    let deps_pb = pb.clone();
+#[derive(Deserialize)]
+struct OrgRepo {
+    full_name: String,
+    #[serde(default)]
+    fork: bool,
+    #[serde(default)]
+    archived: bool,
+}
 
-    while page_count < config.max_pages {
-        page_count += 1;
+/// Lists the public repositories of an organization through the REST API at
+/// `api_url`, skipping forks and archived repositories.
+pub async fn list_org_repos(
+    fetcher: &dyn Fetcher, 
+    api_url: &str, 
+    org: &str, 
+    cache_ttl: Option<Duration>
+) -> Result<Vec<String>, AppError> {
+    let mut repos = Vec::new();
+    
+    for page in 1.. {
+        let url = format!(
+            "{}/orgs/{}/repos?type=public&per_page={}&page={}", 
+            api_url, org, ORG_REPOS_PER_PAGE, page
+        );
+        let body = cached_fetch(fetcher, &url, cache_ttl).await?;
+        let page_repos: Vec<OrgRepo> = serde_json::from_str(&body).map_err(|e| AppError::ParseFailure {
+            url: url.clone(),
+            reason: e.to_string(),
+        })?;
+        let is_last = page_repos.len() < ORG_REPOS_PER_PAGE;
         
-        match cached_fetch(&client, &page_url, config.use_cache).await {
-            Ok(html) => {
-                let (deps, next_link) = parse_page(&html);
-                if deps.is_empty() {
-                    break;
-                }
-                
-                all_dependents.extend(deps);
-                pb.set_position(page_count as u64);
-                deps_pb.set_position(all_dependents.len() as u64);
-                
-                if let Some(link) = next_link {
-                    // Fix the URL construction
-                    if link.starts_with("http") {
-                        page_url = link;
-                    } else if link.starts_with('/') {
-                        page_url = format!("{}{}", GITHUB_BASE_URL, link);
-                    } else {
-                        page_url = format!("{}/{}", GITHUB_BASE_URL, link);
+        repos.extend(page_repos.into_iter()
+            .filter(|repo| !repo.fork && !repo.archived)
+            .map(|repo| repo.full_name));
+        
+        if is_last {
+            break;
+        }
+    }
+    
+    Ok(repos)
+}
+
+/// Deduplicated dependents above the star threshold, sorted by stars.
+pub struct Crawl {
+    pub dependents: Vec<Dependent>,
+    /// Dependents listed on the crawled pages, including duplicates
+    pub total_repos_count: usize,
+    /// Dependents count from the page header
+    pub max_deps: usize,
+    pub header: HeaderCounts,
+    /// Dependents above the star threshold and matching the filters, before
+    /// forks are collapsed
+    pub matched_count: usize,
+    /// Distinct projects once forks are collapsed, set with --collapse-forks
+    pub unique_upstreams: Option<usize>,
+}
+
+/// A fetcher and rate limiter shared by every crawl of a run.
+#[derive(Clone)]
+pub struct Crawler {
+    fetcher: Arc<dyn Fetcher>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl Crawler {
+    /// A crawler fetching pages over HTTP, or from fixtures when
+    /// `options.fixtures` is set.
+    pub fn new(options: &ClientOptions) -> Result<Self, AppError> {
+        let live: Arc<dyn Fetcher> = Arc::new(create_client_with(options)?);
+        let fetcher = match &options.fixtures {
+            Some(mode) => fixture_fetcher(mode, live)?,
+            None => live,
+        };
+        Ok(Crawler::with_fetcher(fetcher))
+    }
+
+    /// A crawler fetching pages with `fetcher`, e.g. a fake in tests or a
+    /// client with middleware.
+    pub fn with_fetcher(fetcher: Arc<dyn Fetcher>) -> Self {
+        Crawler {
+            fetcher,
+            limiter: Arc::new(RateLimiter::new(PAGE_INTERVAL, MAX_CONCURRENT_REQUESTS)),
+        }
+    }
+
+    pub fn fetcher(&self) -> &dyn Fetcher {
+        self.fetcher.as_ref()
+    }
+
+    /// Collects every dependent of the configured repository.
+    pub async fn crawl(&self, config: &Config) -> Result<Crawl, AppError> {
+        let start_time = Instant::now();
+        let mut page_url = config.first_page_url();
+        
+        let mut all_dependents = Vec::new();
+        let mut page_count = 0;
+        
+        // The first page has the maximum number of dependents in its header
+        self.limiter.wait().await;
+        let first_page = cached_fetch(self.fetcher(), &page_url, config.cache_ttl()).await?;
+        let header = parse_header_counts(&first_page);
+        let max_deps = header.for_type(config.dependent_type()).unwrap_or(0);
+        if max_deps > 0 && !config.quiet {
+            eprintln!("Found {} total dependents", max_deps);
+        }
+        let total = std::cmp::min(max_deps, config.max_pages * REPOS_PER_PAGE);
+        let mut progress = Progress::new(config.progress, total as u64);
+
+        let mut prefetched = Some(first_page);
+        while page_count < config.max_pages {
+            page_count += 1;
+            
+            let page = match prefetched.take() {
+                Some(html) => Ok(html),
+                None => {
+                    // Space out page requests to be nice to GitHub
+                    self.limiter.wait().await;
+                    cached_fetch(self.fetcher(), &page_url, config.cache_ttl()).await
+                },
+            };
+            match page {
+                Ok(html) => {
+                    let (deps, next_link) = parse_page(&html);
+                    if deps.is_empty() {
+                        check_empty_page(config, &page_url, page_count, &html)?;
+                        break;
+                    }
+                    if page_count == 1 && header.for_type(config.dependent_type()).is_none() {
+                        eprintln!("Warning: Could not read the dependents count of {}/{}; \
+                            GitHub may have changed its layout", config.owner, config.repo);
                     }
                     
-                    // Add a small delay to be nice to GitHub
-                    sleep(Duration::from_secs(1)).await;
-                } else {
+                    all_dependents.extend(deps);
+                    progress.set_position(all_dependents.len() as u64);
+                    
+                    if let Some(link) = next_link {
+                        // Fix the URL construction
+                        if link.starts_with("http") {
+                            page_url = link;
+                        } else if link.starts_with('/') {
+                            page_url = format!("{}{}", config.base_url, link);
+                        } else {
+                            page_url = format!("{}/{}", config.base_url, link);
+                        }
+                    } else {
+                        break;
+                    }
+                },
+                // Without the first page there is nothing to report, e.g. for a
+                // repository that does not exist
+                Err(e) if page_count == 1 => return Err(e),
+                Err(e) => {
+                    eprintln!("Error fetching page {}: {}", page_count, e);
                     break;
                 }
-            },
-            Err(e) => {
-                println!("Error fetching page {}: {}", page_count, e);
-                break;
             }
         }
-    }
-    
-    pb.finish_with_message("Download complete");
-    
-    println!("\nSorting {} repositories by star count...", all_dependents.len());
-    
-    // Store the length before moving all_dependents
-    let total_repos_count = all_dependents.len();
-    
-    // Remove duplicates while keeping highest star count
-    let mut unique_deps: HashMap<String, String> = HashMap::new();
-    for (repo, stars) in &all_dependents {
-        let current_stars = convert_stars_to_number(stars);
-        if !unique_deps.contains_key(repo) || 
-           current_stars > convert_stars_to_number(unique_deps.get(repo).unwrap()) {
-            unique_deps.insert(repo.clone(), stars.clone());
+        
+        progress.finish();
+        
+        if !config.quiet {
+            eprintln!("\nSorting {} repositories by star count...", all_dependents.len());
         }
+        
+        // Store the length before moving all_dependents
+        let total_repos_count = all_dependents.len();
+        
+        // Remove duplicates while keeping highest star count
+        let mut unique_deps: HashMap<String, Dependent> = HashMap::new();
+        for (repo, stars, forks) in all_dependents {
+            let current_stars = convert_stars_to_number(&stars);
+            let is_better = match unique_deps.get(&repo) {
+                Some(existing) => current_stars > convert_stars_to_number(&existing.stars),
+                None => true,
+            };
+            if is_better {
+                unique_deps.insert(repo.clone(), Dependent {
+                    repo,
+                    stars,
+                    forks,
+                    description: None,
+                    dependent_type: config.dependent_kind(),
+                    fork_of: None,
+                    collapsed_forks: 0,
+                    metadata: None,
+                });
+            }
+        }
+        
+        // Filter by minimum stars and convert to Vec
+        let mut dependents: Vec<Dependent> = unique_deps.into_values()
+            .filter(|dep| convert_stars_to_number(&dep.stars) >= config.min_stars)
+            .collect();
+        
+        // Metadata filters need the repository pages
+        let with_metadata = config.needs_metadata() && !dependents.is_empty();
+        if with_metadata {
+            if !config.quiet {
+                eprintln!("Reading {} repository pages...", dependents.len());
+            }
+            dependents = self.get_repo_pages(config, dependents).await;
+        }
+        dependents.retain(|dep| config.filters.iter().all(|filter| filter.matches(dep)));
+        
+        // Sort by star count
+        sort_by_stars(&mut dependents);
+        
+        let matched_count = dependents.len();
+        let mut unique_upstreams = None;
+        if config.collapse_forks && !dependents.is_empty() {
+            if !with_metadata {
+                if !config.quiet {
+                    eprintln!("Checking {} repositories for forks...", dependents.len());
+                }
+                dependents = self.get_repo_pages(config, dependents).await;
+            }
+            dependents = collapse_forks(dependents);
+            unique_upstreams = Some(dependents.len());
+        }
+        
+        metrics().record_crawl(
+            &format!("{}/{}", config.owner, config.repo).to_lowercase(),
+            config.dependent_kind().name(),
+            config.package.as_deref(),
+            start_time.elapsed(),
+            dependents.len(),
+        );
+        Ok(Crawl {
+            dependents,
+            total_repos_count,
+            max_deps,
+            header,
+            matched_count,
+            unique_upstreams,
+        })
     }
-    
-    // Filter by minimum stars and convert to Vec
-    let mut filtered_deps: Vec<(String, String)> = unique_deps.into_iter()
-        .filter(|(_, stars)| convert_stars_to_number(stars) >= config.min_stars)
-        .collect();
-    
-    // Store the length before moving filtered_deps
-    let more_than_zero_count = filtered_deps.len();
-    
-    // Sort by star count
-    filtered_deps.sort_by(|(_, a_stars), (_, b_stars)| {
-        convert_stars_to_number(b_stars).partial_cmp(&convert_stars_to_number(a_stars)).unwrap()
-    });
-    
-    // Take top N
-    let top_deps = filtered_deps.into_iter().take(config.top_n).collect::<Vec<_>>();
-    
-    // Add descriptions if requested
-    let mut result = Vec::new();
-    
-    if config.show_desc && !top_deps.is_empty() {
-        println!("Fetching repository descriptions...");
+
+    /// Lists the packages of the configured repository.
+    pub async fn list_packages(&self, config: &Config) -> Result<Vec<PackageOption>, AppError> {
+        let url = format!("{}?dependent_type={}", config.dependents_url(), config.dependent_type());
+        let html = cached_fetch(self.fetcher(), &url, config.cache_ttl()).await?;
+        Ok(parse_packages(&html))
+    }
+
+    /// One configuration per package to crawl: every package with
+    /// `--all-packages`, the one named by `--package`, or `config` itself.
+    pub async fn package_configs(&self, config: &Config) -> Result<Vec<Config>, AppError> {
+        if !config.all_packages && config.package.is_none() {
+            return Ok(vec![config.clone()]);
+        }
         
-        let descriptions = get_repo_descriptions(&client, top_deps, config.use_cache).await;
-        for (repo, stars, description) in descriptions {
-            result.push(Dependent {
-                repo,
-                stars,
-                description,
-            });
+        let packages = self.list_packages(config).await?;
+        if config.all_packages {
+            if packages.is_empty() {
+                return Ok(vec![config.clone()]);
+            }
+            return Ok(packages.iter().map(|package| config.with_package(package)).collect());
         }
-    } else {
-        for (repo, stars) in top_deps {
-            result.push(Dependent {
-                repo,
-                stars,
-                description: None,
-            });
+        
+        let name = config.package.as_deref().unwrap_or_default();
+        match packages.iter().find(|package| package.name == name) {
+            Some(package) => Ok(vec![config.with_package(package)]),
+            None => {
+                let names: Vec<&str> = packages.iter().map(|package| package.name.as_str()).collect();
+                Err(AppError::Other(format!(
+                    "{}/{} has no package '{}'. Available packages: {}", 
+                    config.owner, 
+                    config.repo, 
+                    name, 
+                    if names.is_empty() { "none".to_string() } else { names.join(", ") }
+                )))
+            }
         }
     }
-    
-    Ok((result, total_repos_count, more_than_zero_count, max_deps))
+
+    /// Takes the top N of a crawl and adds descriptions if requested and
+    /// the repository pages were not read during the crawl.
+    pub async fn top_dependents(&self, config: &Config, crawl: &Crawl) -> Vec<Dependent> {
+        let top_deps = crawl.dependents.iter().take(config.top_n).cloned().collect::<Vec<_>>();
+        
+        if config.show_desc && top_deps.iter().any(|dep| dep.metadata.is_none()) {
+            if !config.quiet {
+                eprintln!("Fetching repository descriptions...");
+            }
+            
+            self.get_repo_descriptions(&config.base_url, top_deps, config.cache_ttl()).await
+        } else {
+            top_deps
+        }
+    }
+
+    /// Reads the page of every dependent for its description, upstream and
+    /// metadata. Dependents whose page cannot be read are left without.
+    async fn get_repo_pages(&self, config: &Config, dependents: Vec<Dependent>) -> Vec<Dependent> {
+        let base_url = config.base_url.as_str();
+        let cache_ttl = config.cache_ttl();
+        stream::iter(dependents)
+            .map(|mut dep| async move {
+                let _permit = self.limiter.acquire().await;
+                if let Some(page) = get_repo_page(self.fetcher(), base_url, &dep.repo, cache_ttl).await {
+                    dep.description = page.description;
+                    dep.fork_of = page.fork_of;
+                    dep.metadata = Some(page.metadata);
+                }
+                dep
+            })
+            .buffered(MAX_CONCURRENT_REQUESTS)
+            .collect::<Vec<_>>()
+            .await
+    }
+
+    async fn get_repo_descriptions(
+        &self, 
+        base_url: &str, 
+        dependents: Vec<Dependent>, 
+        cache_ttl: Option<Duration>
+    ) -> Vec<Dependent> {
+        // Process in parallel with rate limiting, keeping the star ordering
+        stream::iter(dependents)
+            .map(|mut dep| async move {
+                let _permit = self.limiter.acquire().await;
+                dep.description = get_repo_description(self.fetcher(), base_url, &dep.repo, cache_ttl).await;
+                dep
+            })
+            .buffered(MAX_CONCURRENT_REQUESTS)
+            .collect::<Vec<_>>()
+            .await
+    }
+}
+
+fn sort_by_stars(dependents: &mut [Dependent]) {
+    dependents.sort_by(|a, b| {
+        convert_stars_to_number(&b.stars).partial_cmp(&convert_stars_to_number(&a.stars)).unwrap()
+    });
 }
 
-async fn get_repo_descriptions(
-    client: &Client, 
-    repos: Vec<(String, String)>, 
-    use_cache: bool
-) -> Vec<(String, String, Option<String>)> {
-    println!("Fetching repository descriptions...");
+/// Collapses forks into one entry per upstream project, following chains of
+/// forks found among the dependents. The entry is the upstream itself when it
+/// is a dependent, otherwise its most starred fork; `collapsed_forks` counts
+/// the other members of the group. The result is sorted by stars.
+pub fn collapse_forks(dependents: Vec<Dependent>) -> Vec<Dependent> {
+    let parents: HashMap<String, String> = dependents.iter()
+        .filter_map(|dep| Some((dep.repo.clone(), dep.fork_of.clone()?)))
+        .collect();
+    let upstream_of = |repo: &str| {
+        let mut upstream = repo.to_string();
+        // Bounded so a cycle cannot loop forever
+        for _ in 0..parents.len() {
+            match parents.get(&upstream) {
+                Some(parent) => upstream = parent.clone(),
+                None => break,
+            }
+        }
+        upstream
+    };
     
-    // Create a rate limiter with max 5 concurrent requests
-    let semaphore = Arc::new(Semaphore::new(5));
+    let mut sorted = dependents;
+    sort_by_stars(&mut sorted);
+    let mut groups: Vec<Dependent> = Vec::new();
+    let mut group_index: HashMap<String, usize> = HashMap::new();
+    for dep in sorted {
+        let upstream = upstream_of(&dep.repo);
+        match group_index.get(&upstream) {
+            Some(&idx) if dep.repo == upstream => {
+                let collapsed_forks = groups[idx].collapsed_forks + 1;
+                groups[idx] = Dependent { collapsed_forks, ..dep };
+            },
+            Some(&idx) => groups[idx].collapsed_forks += 1,
+            None => {
+                group_index.insert(upstream, groups.len());
+                groups.push(dep);
+            },
+        }
+    }
     
-    let client = Arc::new(client.clone());
+    sort_by_stars(&mut groups);
+    groups
+}
+
+/// Combines crawls of several dependent types into one list sorted by stars.
+pub fn merge_crawls(crawls: Vec<Crawl>) -> Crawl {
+    let mut merged = Crawl {
+        dependents: Vec::new(),
+        total_repos_count: 0,
+        max_deps: 0,
+        header: HeaderCounts::default(),
+        matched_count: 0,
+        unique_upstreams: None,
+    };
+    for crawl in crawls {
+        merged.dependents.extend(crawl.dependents);
+        merged.total_repos_count += crawl.total_repos_count;
+        merged.max_deps += crawl.max_deps;
+        merged.matched_count += crawl.matched_count;
+        merged.header = crawl.header;
+        if let Some(count) = crawl.unique_upstreams {
+            merged.unique_upstreams = Some(merged.unique_upstreams.unwrap_or(0) + count);
+        }
+    }
+    sort_by_stars(&mut merged.dependents);
+    merged
+}
+
+pub async fn get_top_dependents(
+    config: &Config,
+) -> Result<(Vec<Dependent>, usize, usize, usize), Box<dyn std::error::Error>> {
+    let crawler = Crawler::new(&config.client_options()?)?;
+    let crawl = crawler.crawl(config).await?;
     
-    // Process in parallel with rate limiting
-    let results = stream::iter(repos)
-        .map(|(repo, stars)| {
-            let client = client.clone();
-            let semaphore = semaphore.clone();
-            
-            async move {
-                let _permit = semaphore.acquire().await.unwrap();
-                let description = get_repo_description(&client, &repo, use_cache).await;
-                (repo, stars, description)
-            }
-        })
-        .buffer_unordered(5) // Process up to 5 at a time
-        .collect::<Vec<_>>()
-        .await;
+    let result = crawler.top_dependents(config, &crawl).await;
     
-    results
-} 
\ No newline at end of file
+    Ok((result, crawl.total_repos_count, crawl.matched_count, crawl.max_deps))
+}
@@ -0,0 +1,177 @@
+use clap::ValueEnum;
+
+use crate::dependent::convert_stars_to_number;
+use crate::tree::{DependencyTree, TreeEdge, TreeNode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// GraphML, e.g. for Gephi
+    Graphml,
+    /// JSON Graph Format
+    Jgf,
+}
+
+/// Renders the repositories of a tree as nodes and their dependencies as
+/// edges from dependent to dependency. Nodes carry stars, forks, language
+/// and depth; edges carry the dependent type and the package.
+pub fn render_graph(tree: &DependencyTree, format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Dot => render_dot(tree),
+        GraphFormat::Graphml => render_graphml(tree),
+        GraphFormat::Jgf => render_jgf(tree),
+    }
+}
+
+/// The attributes of a node that are known, as (name, value, is_number).
+fn node_attributes(node: &TreeNode) -> Vec<(&'static str, String, bool)> {
+    let dep = &node.dependent;
+    let mut attributes = vec![
+        ("stars", convert_stars_to_number(&dep.stars).to_string(), true),
+    ];
+    if let Some(forks) = &dep.forks {
+        attributes.push(("forks", convert_stars_to_number(forks).to_string(), true));
+    }
+    if let Some(language) = dep.metadata.as_ref().and_then(|metadata| metadata.language.clone()) {
+        attributes.push(("language", language, false));
+    }
+    attributes.push(("depth", node.depth.to_string(), true));
+    if let Some(total) = node.total_dependents {
+        attributes.push(("dependents", total.to_string(), true));
+    }
+    attributes
+}
+
+fn edge_attributes(edge: &TreeEdge) -> Vec<(&'static str, String)> {
+    let mut attributes = vec![("type", edge.dependent_type.name().to_string())];
+    if let Some(package) = &edge.package {
+        attributes.push(("package", package.clone()));
+    }
+    attributes
+}
+
+fn dot_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn render_dot(tree: &DependencyTree) -> String {
+    let mut out = String::from("digraph dependents {\n");
+    out.push_str(&format!("  {} [root=true, dependents={}];\n", dot_string(&tree.root), tree.root_total));
+    for node in &tree.nodes {
+        let attributes: Vec<String> = node_attributes(node).into_iter()
+            .map(|(name, value, is_number)| {
+                format!("{}={}", name, if is_number { value } else { dot_string(&value) })
+            })
+            .collect();
+        out.push_str(&format!("  {} [{}];\n", dot_string(&node.dependent.repo), attributes.join(", ")));
+    }
+    for edge in &tree.edges {
+        let attributes: Vec<String> = edge_attributes(edge).into_iter()
+            .map(|(name, value)| format!("{}={}", name, dot_string(&value)))
+            .collect();
+        out.push_str(&format!("  {} -> {} [{}];\n",
+            dot_string(&edge.dependent),
+            dot_string(&edge.dependency),
+            attributes.join(", ")));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn xml_escape(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn render_graphml(tree: &DependencyTree) -> String {
+    const KEYS: [(&str, &str, &str); 7] = [
+        ("stars", "node", "double"),
+        ("forks", "node", "double"),
+        ("language", "node", "string"),
+        ("depth", "node", "int"),
+        ("dependents", "node", "int"),
+        ("type", "edge", "string"),
+        ("package", "edge", "string"),
+    ];
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+    for (name, target, kind) in KEYS {
+        out.push_str(&format!(
+            "  <key id=\"{0}\" for=\"{1}\" attr.name=\"{0}\" attr.type=\"{2}\"/>\n", name, target, kind
+        ));
+    }
+    out.push_str(&format!("  <graph id=\"{}\" edgedefault=\"directed\">\n", xml_escape(&tree.root)));
+    out.push_str(&format!(
+        "    <node id=\"{}\"><data key=\"dependents\">{}</data></node>\n", xml_escape(&tree.root), tree.root_total
+    ));
+    for node in &tree.nodes {
+        let data: String = node_attributes(node).into_iter()
+            .map(|(name, value, _)| format!("<data key=\"{}\">{}</data>", name, xml_escape(&value)))
+            .collect();
+        out.push_str(&format!("    <node id=\"{}\">{}</node>\n", xml_escape(&node.dependent.repo), data));
+    }
+    for edge in &tree.edges {
+        let data: String = edge_attributes(edge).into_iter()
+            .map(|(name, value)| format!("<data key=\"{}\">{}</data>", name, xml_escape(&value)))
+            .collect();
+        out.push_str(&format!("    <edge source=\"{}\" target=\"{}\">{}</edge>\n",
+            xml_escape(&edge.dependent),
+            xml_escape(&edge.dependency),
+            data));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+fn render_jgf(tree: &DependencyTree) -> String {
+    let mut nodes = serde_json::Map::new();
+    nodes.insert(tree.root.clone(), serde_json::json!({
+        "label": tree.root,
+        "metadata": { "root": true, "dependents": tree.root_total },
+    }));
+    for node in &tree.nodes {
+        let metadata: serde_json::Map<String, serde_json::Value> = node_attributes(node).into_iter()
+            .map(|(name, value, is_number)| {
+                let value = match value.parse::<f64>() {
+                    Ok(number) if is_number => serde_json::json!(number),
+                    _ => serde_json::Value::from(value),
+                };
+                (name.to_string(), value)
+            })
+            .collect();
+        nodes.insert(node.dependent.repo.clone(), serde_json::json!({
+            "label": node.dependent.repo,
+            "metadata": metadata,
+        }));
+    }
+    let edges: Vec<serde_json::Value> = tree.edges.iter()
+        .map(|edge| {
+            let metadata: serde_json::Map<String, serde_json::Value> = edge_attributes(edge).into_iter()
+                .map(|(name, value)| (name.to_string(), serde_json::Value::from(value)))
+                .collect();
+            serde_json::json!({
+                "source": edge.dependent,
+                "target": edge.dependency,
+                "relation": "depends on",
+                "metadata": metadata,
+            })
+        })
+        .collect();
+
+    let graph = serde_json::json!({
+        "graph": {
+            "id": tree.root,
+            "label": format!("Dependents of {}", tree.root),
+            "directed": true,
+            "nodes": nodes,
+            "edges": edges,
+        }
+    });
+    // Serializing a JSON value cannot fail
+    format!("{}\n", serde_json::to_string_pretty(&graph).unwrap_or_default())
+}
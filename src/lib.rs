@@ -1,10 +1,27 @@
+pub mod batch;
 pub mod cache;
+pub mod cli;
 pub mod client;
+pub mod commands;
 pub mod config;
 pub mod dependent;
 pub mod error;
+pub mod fetcher;
+pub mod filter;
+pub mod fixtures;
 pub mod github;
+pub mod graph;
+pub mod metrics;
 pub mod output;
+pub mod progress;
+pub mod selectors;
+pub mod serve;
+pub mod settings;
+pub mod snapshot;
+pub mod template;
+pub mod tree;
+pub mod trend;
+pub mod watch;
 
 #[cfg(test)]
 mod tests; 
\ No newline at end of file
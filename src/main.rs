@@ -1,101 +1,19 @@
-mod cache;
-mod client;
-mod config;
-mod dependent;
-mod error;
-mod github;
-mod output;
-#[cfg(test)]
-mod tests;
-use clap::{Arg, ArgAction, Command};
-use std::time::Instant;
+use clap::Parser;
 
-use config::Config;
-use github::get_top_dependents;
-use output::display_results;
+use ghtopdep_rs::cli::{normalize_args, Cli};
+use ghtopdep_rs::commands;
+use ghtopdep_rs::error::{exit_code, AppError};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let matches = Command::new("ghtodep-rs")
-        .version("1.0")
-        .about("Get top dependent repositories for a GitHub project")
-        .arg(
-            Arg::new("repo_url")
-                .help("GitHub repository URL or owner/repo format")
-                .required(true)
-                .index(1)
-        )
-        .arg(
-            Arg::new("top_n")
-                .long("rows")
-                .help("Number of top dependents to fetch")
-                .default_value("10")
-        )
-        .arg(
-            Arg::new("max_pages")
-                .long("max_pages")
-                .help("Maximum number of pages to fetch")
-                .default_value("100")
-        )
-        .arg(
-            Arg::new("min_stars")
-                .long("minstar")
-                .help("Minimum number of stars")
-                .default_value("0")
-        )
-        .arg(
-            Arg::new("packages")
-                .long("packages")
-                .help("Show package dependents instead of repositories")
-                .action(ArgAction::SetTrue)
-        )
-        .arg(
-            Arg::new("description")
-                .long("description")
-                .help("Show repository descriptions")
-                .action(ArgAction::SetTrue)
-        )
-        .arg(
-            Arg::new("no-cache")
-                .long("no-cache")
-                .help("Disable caching")
-                .action(ArgAction::SetTrue)
-        )
-        .arg(
-            Arg::new("format")
-                .long("format")
-                .help("Output format (text, json, table)")
-                .default_value(config::DEFAULT_FORMAT)
-        )
-        .arg(
-            Arg::new("table")
-                .long("table")
-                .help("Use table output format (shorthand for --format table)")
-                .action(ArgAction::SetTrue)
-        )
-        .get_matches();
+async fn main() {
+    let cli = Cli::parse_from(normalize_args(std::env::args_os()));
 
-    let config = Config::from_matches(&matches)?;
-    println!("Fetching {} dependents for {}/{}...", 
-        if config.is_package { "package" } else { "repository" }, 
-        config.owner, 
-        config.repo);
-
-    let start_time = Instant::now();
-
-    let (top_dependents, total_repos_count, more_than_zero_count, max_deps) = 
-        get_top_dependents(&config).await?;
-
-    let elapsed = start_time.elapsed();
-    
-    display_results(
-        &config,
-        &top_dependents,
-        total_repos_count,
-        more_than_zero_count,
-        max_deps,
-        elapsed.as_secs_f64(),
-    )?;
-
-    Ok(())
-}
\ No newline at end of file
+    if let Err(err) = commands::run(cli).await {
+        if let Some(usage_err) = err.downcast_ref::<clap::Error>() {
+            usage_err.exit();
+        }
+        eprintln!("{}", err);
+        let code = err.downcast_ref::<AppError>().map_or(exit_code::FAILURE, AppError::exit_code);
+        std::process::exit(code);
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt::Write as _;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Response, Server, StatusCode};
+
+use crate::error::AppError;
+
+/// The content type of the Prometheus text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// How a response cache lookup went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheResult {
+    Hit,
+    /// No entry, or an expired one
+    Miss,
+    /// An entry that could not be read and was fetched again
+    Corrupt,
+}
+
+/// Why a request was sent again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RetryReason {
+    RateLimited,
+    Network,
+}
+
+impl RetryReason {
+    fn label(&self) -> &'static str {
+        match self {
+            RetryReason::RateLimited => "rate_limited",
+            RetryReason::Network => "network",
+        }
+    }
+}
+
+/// A crawled dependents list: (target, type, package).
+type CrawlLabels = (String, &'static str, Option<String>);
+
+#[derive(Debug, Clone, Copy, Default)]
+struct CrawlStats {
+    crawls: u64,
+    last_duration: Duration,
+    last_dependents: usize,
+}
+
+/// Counters of the requests, cache lookups and crawls of a run.
+pub struct Metrics {
+    pages_fetched: AtomicU64,
+    bytes_downloaded: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    cache_corruptions: AtomicU64,
+    responses: Mutex<BTreeMap<u16, u64>>,
+    retries: Mutex<BTreeMap<RetryReason, u64>>,
+    crawls: Mutex<BTreeMap<CrawlLabels, CrawlStats>>,
+}
+
+static METRICS: Metrics = Metrics::new();
+
+/// The metrics every request and crawl of this process is recorded in.
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics::new()
+    }
+}
+
+impl Metrics {
+    pub const fn new() -> Self {
+        Metrics {
+            pages_fetched: AtomicU64::new(0),
+            bytes_downloaded: AtomicU64::new(0),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            cache_corruptions: AtomicU64::new(0),
+            responses: Mutex::new(BTreeMap::new()),
+            retries: Mutex::new(BTreeMap::new()),
+            crawls: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Records a response from GitHub, whatever its status.
+    pub fn record_response(&self, status: u16) {
+        *self.responses.lock().unwrap().entry(status).or_default() += 1;
+    }
+
+    /// Records a page downloaded successfully.
+    pub fn record_page(&self, bytes: usize) {
+        self.pages_fetched.fetch_add(1, Ordering::Relaxed);
+        self.bytes_downloaded.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_retry(&self, reason: RetryReason) {
+        *self.retries.lock().unwrap().entry(reason).or_default() += 1;
+    }
+
+    pub fn record_cache(&self, result: CacheResult) {
+        let counter = match result {
+            CacheResult::Hit => &self.cache_hits,
+            CacheResult::Miss => &self.cache_misses,
+            CacheResult::Corrupt => &self.cache_corruptions,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a finished crawl of the dependents of `target` as `owner/repo`.
+    pub fn record_crawl(
+        &self,
+        target: &str,
+        dependent_type: &'static str,
+        package: Option<&str>,
+        duration: Duration,
+        dependents: usize,
+    ) {
+        let labels = (target.to_string(), dependent_type, package.map(str::to_string));
+        let mut crawls = self.crawls.lock().unwrap();
+        let stats = crawls.entry(labels).or_default();
+        stats.crawls += 1;
+        stats.last_duration = duration;
+        stats.last_dependents = dependents;
+    }
+
+    pub fn pages_fetched(&self) -> u64 {
+        self.pages_fetched.load(Ordering::Relaxed)
+    }
+
+    pub fn cache_lookups(&self, result: CacheResult) -> u64 {
+        match result {
+            CacheResult::Hit => &self.cache_hits,
+            CacheResult::Miss => &self.cache_misses,
+            CacheResult::Corrupt => &self.cache_corruptions,
+        }.load(Ordering::Relaxed)
+    }
+
+    pub fn retries(&self, reason: RetryReason) -> u64 {
+        self.retries.lock().unwrap().get(&reason).copied().unwrap_or(0)
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+
+        family(&mut out, "ghtopdep_pages_fetched_total", "counter", "Pages downloaded from GitHub");
+        sample(&mut out, "ghtopdep_pages_fetched_total", &[], load(&self.pages_fetched));
+        family(&mut out, "ghtopdep_downloaded_bytes_total", "counter", "Bytes of pages downloaded from GitHub");
+        sample(&mut out, "ghtopdep_downloaded_bytes_total", &[], load(&self.bytes_downloaded));
+
+        family(&mut out, "ghtopdep_http_responses_total", "counter", "Responses from GitHub by status code");
+        for (status, count) in self.responses.lock().unwrap().iter() {
+            sample(&mut out, "ghtopdep_http_responses_total", &[("code", &status.to_string())], count);
+        }
+        family(&mut out, "ghtopdep_http_retries_total", "counter", "Requests sent again after a rate limit or network error");
+        for (reason, count) in self.retries.lock().unwrap().iter() {
+            sample(&mut out, "ghtopdep_http_retries_total", &[("reason", reason.label())], count);
+        }
+
+        family(&mut out, "ghtopdep_cache_lookups_total", "counter", "Response cache lookups by result");
+        for (result, counter) in [
+            ("hit", &self.cache_hits),
+            ("miss", &self.cache_misses),
+            ("corrupt", &self.cache_corruptions),
+        ] {
+            sample(&mut out, "ghtopdep_cache_lookups_total", &[("result", result)], load(counter));
+        }
+
+        let crawls = self.crawls.lock().unwrap();
+        let crawl_labels = |(target, dependent_type, package): &CrawlLabels| {
+            let mut labels = vec![("target", target.clone()), ("type", dependent_type.to_string())];
+            if let Some(package) = package {
+                labels.push(("package", package.clone()));
+            }
+            labels
+        };
+        family(&mut out, "ghtopdep_crawls_total", "counter", "Dependents lists crawled");
+        for (labels, stats) in crawls.iter() {
+            sample(&mut out, "ghtopdep_crawls_total", &borrowed(&crawl_labels(labels)), stats.crawls);
+        }
+        family(&mut out, "ghtopdep_crawl_duration_seconds", "gauge", "Duration of the latest crawl");
+        for (labels, stats) in crawls.iter() {
+            let seconds = stats.last_duration.as_secs_f64();
+            sample(&mut out, "ghtopdep_crawl_duration_seconds", &borrowed(&crawl_labels(labels)), seconds);
+        }
+        family(&mut out, "ghtopdep_dependents_found", "gauge", "Dependents kept by the latest crawl");
+        for (labels, stats) in crawls.iter() {
+            sample(&mut out, "ghtopdep_dependents_found", &borrowed(&crawl_labels(labels)), stats.last_dependents);
+        }
+
+        out
+    }
+}
+
+fn borrowed<'a>(labels: &'a [(&'static str, String)]) -> Vec<(&'static str, &'a str)> {
+    labels.iter().map(|(name, value)| (*name, value.as_str())).collect()
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+    let labels: Vec<String> = labels.iter()
+        .map(|(label, value)| format!("{}=\"{}\"", label, escape_label(value)))
+        .collect();
+    if labels.is_empty() {
+        let _ = writeln!(out, "{} {}", name, value);
+    } else {
+        let _ = writeln!(out, "{}{{{}}} {}", name, labels.join(","), value);
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Writes the metrics for the node exporter's textfile collector. The file
+/// is replaced in one step so the collector never reads half of it.
+pub fn write_textfile(metrics: &Metrics, path: &Path) -> Result<(), AppError> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    fs::write(&temp_path, metrics.render())?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// The response to a `GET /metrics`.
+pub fn metrics_response() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", CONTENT_TYPE)
+        .body(Body::from(metrics().render()))
+        .unwrap_or_default()
+}
+
+/// Serves `/metrics` until interrupted.
+pub async fn serve_metrics(bind: SocketAddr) -> Result<(), AppError> {
+    let make_service = make_service_fn(|_| async {
+        Ok::<_, Infallible>(service_fn(|request: hyper::Request<Body>| async move {
+            let response = if request.uri().path() == "/metrics" {
+                metrics_response()
+            } else {
+                Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Body::from("Not found\n"))
+                    .unwrap_or_default()
+            };
+            Ok::<_, Infallible>(response)
+        }))
+    });
+    Server::try_bind(&bind)
+        .map_err(|e| AppError::Other(format!("Could not listen on {}: {}", bind, e)))?
+        .serve(make_service)
+        .await
+        .map_err(|e| AppError::Other(format!("Metrics server error: {}", e)))
+}
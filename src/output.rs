@@ -1,9 +1,126 @@
-use prettytable::{Table, row};
-use serde_json;
+use std::collections::HashSet;
+use std::str::FromStr;
+use clap::ValueEnum;
+use prettytable::{Cell, Row, Table};
+use serde::Deserialize;
 
+use crate::batch::BatchReport;
 use crate::config::Config;
 use crate::dependent::Dependent;
 use crate::error::AppError;
+use crate::github::{Crawl, PackageOption};
+use crate::snapshot::{format_timestamp, SnapshotDiff};
+use crate::tree::DependencyTree;
+use crate::trend::{sparkline, TrendPoint};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Table,
+    /// Comma separated values, one row per dependent
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TrendFormat {
+    /// One row per recorded run with the change since the previous one
+    Table,
+    /// ASCII sparkline per dependent type
+    Chart,
+    /// Comma separated values for spreadsheets
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Column {
+    Rank,
+    Repo,
+    Url,
+    Stars,
+    Forks,
+    Description,
+    Type,
+    /// The repository a fork was forked from
+    #[value(name = "fork_of")]
+    ForkOf,
+    /// Forks collapsed into the entry
+    Collapsed,
+    Topics,
+    License,
+    Language,
+    Archived,
+    Homepage,
+    #[value(name = "last_commit")]
+    LastCommit,
+}
+
+impl Column {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Rank => "rank",
+            Column::Repo => "repo",
+            Column::Url => "url",
+            Column::Stars => "stars",
+            Column::Forks => "forks",
+            Column::Description => "description",
+            Column::Type => "type",
+            Column::ForkOf => "fork_of",
+            Column::Collapsed => "collapsed",
+            Column::Topics => "topics",
+            Column::License => "license",
+            Column::Language => "language",
+            Column::Archived => "archived",
+            Column::Homepage => "homepage",
+            Column::LastCommit => "last_commit",
+        }
+    }
+
+    /// The column's value for the dependent at `rank`, linking to `base_url`.
+    pub fn value(&self, base_url: &str, rank: usize, dep: &Dependent) -> String {
+        match self {
+            Column::Rank => rank.to_string(),
+            Column::Repo => dep.repo.clone(),
+            Column::Url => format!("{}/{}", base_url, dep.repo),
+            Column::Stars => dep.stars.clone(),
+            Column::Forks => dep.forks.clone().unwrap_or_default(),
+            Column::Description => dep.description.clone().unwrap_or_default(),
+            Column::Type => dep.dependent_type.name().to_string(),
+            Column::ForkOf => dep.fork_of.clone().unwrap_or_default(),
+            Column::Collapsed => dep.collapsed_forks.to_string(),
+            _ => {
+                let Some(metadata) = &dep.metadata else {
+                    return String::new();
+                };
+                match self {
+                    Column::Topics => metadata.topics.join(", "),
+                    Column::License => metadata.license.clone().unwrap_or_default(),
+                    Column::Language => metadata.language.clone().unwrap_or_default(),
+                    Column::Archived => metadata.archived.to_string(),
+                    Column::Homepage => metadata.homepage.clone().unwrap_or_default(),
+                    _ => metadata.last_commit.clone().unwrap_or_default(),
+                }
+            },
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Column::value_variants().iter()
+            .find(|column| column.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = Column::value_variants().iter().map(|c| c.name()).collect();
+                AppError::Other(format!(
+                    "Unknown column '{}'. Expected one of: {}", s, names.join(", ")
+                ))
+            })
+    }
+}
 
 pub fn display_results(
     config: &Config,
@@ -11,27 +128,30 @@ pub fn display_results(
     total_repos_count: usize,
     more_than_zero_count: usize,
     max_deps: usize,
+    unique_upstreams: Option<usize>,
     elapsed_secs: f64,
 ) -> Result<(), AppError> {
-    match config.output_format.as_str() {
-        "json" => {
-            let result = serde_json::json!({
-                "dependents": top_dependents,
-                "stats": {
-                    "total_repositories": total_repos_count,
-                    "repositories_with_stars": more_than_zero_count,
-                    "elapsed_seconds": elapsed_secs
-                }
-            });
+    match config.output_format {
+        OutputFormat::Json => {
+            let result = results_json(top_dependents, total_repos_count, more_than_zero_count, unique_upstreams, elapsed_secs);
             println!("{}", serde_json::to_string_pretty(&result)?);
         },
-        "table" => {
+        OutputFormat::Csv => {
+            let columns = csv_columns(config);
+            println!("{}", csv_line(columns.iter().map(|c| c.name().to_string())));
+            for (idx, dep) in top_dependents.iter().enumerate() {
+                println!("{}", csv_line(columns.iter().map(|c| c.value(&config.base_url, idx + 1, dep))));
+            }
+        },
+        OutputFormat::Table => {
+            let columns = table_columns(config);
             let mut table = Table::new();
-            table.add_row(row!["url", "stars"]);
+            table.add_row(Row::new(columns.iter().map(|c| Cell::new(c.name())).collect()));
             
-            for dep in top_dependents.iter() {
-                let url = format!("https://github.com/{}", dep.repo);
-                table.add_row(row![url, dep.stars]);
+            for (idx, dep) in top_dependents.iter().enumerate() {
+                table.add_row(Row::new(columns.iter()
+                    .map(|c| Cell::new(&c.value(&config.base_url, idx + 1, dep)))
+                    .collect()));
             }
             
             table.printstd();
@@ -41,36 +161,554 @@ pub fn display_results(
                 if total_repos_count < max_deps { " others repositories are private" } else { "" }
             );
             println!("found {} repositories with more than zero star", more_than_zero_count);
+            if let Some(unique_upstreams) = unique_upstreams {
+                println!("found {} unique upstream projects", unique_upstreams);
+            }
             println!("Completed in {:.2} seconds", elapsed_secs);
         },
-        _ => {
+        OutputFormat::Text => {
             if !top_dependents.is_empty() {
                 println!("\nTop {} {} dependents (min {} stars):", 
                     top_dependents.len(), 
-                    config.dependent_type().to_lowercase(), 
+                    config.type_label(), 
                     config.min_stars);
                 
                 for (idx, dep) in top_dependents.iter().enumerate() {
-                    if config.show_desc {
-                        let desc_text = match &dep.description {
-                            Some(desc) if !desc.is_empty() => format!("\n   {}", desc),
-                            _ => String::new(),
-                        };
-                        println!("{}. {} (⭐ {}){}", idx + 1, dep.repo, dep.stars, desc_text);
-                    } else {
-                        println!("{}. {} (⭐ {})", idx + 1, dep.repo, dep.stars);
-                    }
+                    println!("{}", text_line(config, idx + 1, dep));
                 }
                 
                 println!("\nFound {} total repositories", total_repos_count);
                 println!("Found {} repositories with stars", more_than_zero_count);
+                if let Some(unique_upstreams) = unique_upstreams {
+                    println!("Found {} unique upstream projects", unique_upstreams);
+                }
                 println!("Completed in {:.2} seconds", elapsed_secs);
             } else {
                 println!("No {} dependents found or access denied.", 
-                    config.dependent_type().to_lowercase());
+                    config.type_label());
             }
         }
     }
 
     Ok(())
-} 
\ No newline at end of file
+}
+
+/// The report of one crawl: the top dependents and the run's stats.
+pub fn results_json(
+    top_dependents: &[Dependent],
+    total_repos_count: usize,
+    more_than_zero_count: usize,
+    unique_upstreams: Option<usize>,
+    elapsed_secs: f64,
+) -> serde_json::Value {
+    let mut stats = serde_json::json!({
+        "total_repositories": total_repos_count,
+        "repositories_with_stars": more_than_zero_count,
+        "elapsed_seconds": elapsed_secs
+    });
+    if let Some(unique_upstreams) = unique_upstreams {
+        stats["unique_upstream_projects"] = unique_upstreams.into();
+    }
+    serde_json::json!({
+        "dependents": top_dependents,
+        "stats": stats
+    })
+}
+
+/// The results of one dependent type or package when several are crawled.
+pub struct Section {
+    pub config: Config,
+    pub top_dependents: Vec<Dependent>,
+    pub crawl: Crawl,
+}
+
+/// Prints a section per dependent type or package; JSON output is an object
+/// keyed by section name, e.g. `repositories` and `packages`.
+pub fn display_sections(config: &Config, sections: &[Section], elapsed_secs: f64) -> Result<(), AppError> {
+    if config.output_format == OutputFormat::Json {
+        let result: serde_json::Map<String, serde_json::Value> = sections.iter()
+            .map(|section| (section.config.section_name(), results_json(
+                &section.top_dependents,
+                section.crawl.total_repos_count,
+                section.crawl.matched_count,
+                section.crawl.unique_upstreams,
+                elapsed_secs,
+            )))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    if config.output_format == OutputFormat::Csv {
+        let rows = sections.iter().map(|section| (section.config.section_name(), &section.config, &section.top_dependents[..]));
+        print_csv_groups("section", rows);
+        return Ok(());
+    }
+
+    for section in sections {
+        println!("\n== {} ==", section.config.section_name());
+        display_results(
+            &section.config,
+            &section.top_dependents,
+            section.crawl.total_repos_count,
+            section.crawl.matched_count,
+            section.crawl.max_deps,
+            section.crawl.unique_upstreams,
+            elapsed_secs,
+        )?;
+    }
+    Ok(())
+}
+
+/// Prints a reverse-dependency tree: indented under each crawled repository
+/// in text output, one row per edge in table and CSV output, and nodes and
+/// edges in JSON.
+pub fn display_tree(config: &Config, tree: &DependencyTree, elapsed_secs: f64) -> Result<(), AppError> {
+    match config.output_format {
+        OutputFormat::Json => {
+            let result = serde_json::json!({
+                "root": tree.root,
+                "total_dependents": tree.root_total,
+                "nodes": tree.nodes,
+                "edges": tree.edges,
+                "stats": {
+                    "repositories": tree.nodes.len(),
+                    "crawls": tree.crawls,
+                    "truncated": tree.truncated,
+                    "elapsed_seconds": elapsed_secs
+                }
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+            return Ok(());
+        },
+        OutputFormat::Csv => {
+            println!("dependency,dependent,depth,stars,dependents");
+            for edge in &tree.edges {
+                let node = tree.node(&edge.dependent);
+                println!("{}", csv_line([
+                    edge.dependency.clone(),
+                    edge.dependent.clone(),
+                    optional_count(node.map(|node| node.depth)),
+                    node.map(|node| node.dependent.stars.clone()).unwrap_or_default(),
+                    optional_count(node.and_then(|node| node.total_dependents)),
+                ]));
+            }
+            return Ok(());
+        },
+        OutputFormat::Table => {
+            let mut table = Table::new();
+            table.add_row(Row::new(["depth", "dependency", "url", "stars", "dependents"].iter()
+                .map(|h| Cell::new(h)).collect()));
+            for edge in &tree.edges {
+                let node = tree.node(&edge.dependent);
+                table.add_row(Row::new(vec![
+                    Cell::new(&optional_count(node.map(|node| node.depth))),
+                    Cell::new(&edge.dependency),
+                    Cell::new(&format!("{}/{}", config.base_url, edge.dependent)),
+                    Cell::new(&node.map(|node| node.dependent.stars.clone()).unwrap_or_default()),
+                    Cell::new(&optional_count(node.and_then(|node| node.total_dependents))),
+                ]));
+            }
+            table.printstd();
+        },
+        OutputFormat::Text => {
+            println!("\n{} ({} dependents)", tree.root, tree.root_total);
+            let mut printed = HashSet::from([tree.root.as_str()]);
+            print_subtree(tree, &tree.root, "", &mut printed);
+        },
+    }
+
+    println!("\nFound {} repositories in {} dependents lists{}", 
+        tree.nodes.len(), 
+        tree.crawls, 
+        if tree.truncated { " (budget reached)" } else { "" });
+    println!("Completed in {:.2} seconds", elapsed_secs);
+    Ok(())
+}
+
+/// Prints the dependents of `repo` below it. A repository reached a second
+/// time is marked instead of repeating its dependents.
+fn print_subtree<'a>(tree: &'a DependencyTree, repo: &str, prefix: &str, printed: &mut HashSet<&'a str>) {
+    let children = tree.children(repo);
+    for (idx, edge) in children.iter().enumerate() {
+        let is_last = idx + 1 == children.len();
+        let mut label = edge.dependent.clone();
+        if let Some(node) = tree.node(&edge.dependent) {
+            label.push_str(&format!(" (⭐ {}", node.dependent.stars));
+            if let Some(total) = node.total_dependents {
+                label.push_str(&format!(", {} dependents", total));
+            }
+            label.push(')');
+        }
+        let is_repeat = !printed.insert(edge.dependent.as_str());
+        if is_repeat {
+            label.push_str(" (see above)");
+        }
+        println!("{}{}{}", prefix, if is_last { "└── " } else { "├── " }, label);
+        if !is_repeat {
+            let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+            print_subtree(tree, &edge.dependent, &child_prefix, printed);
+        }
+    }
+}
+
+pub fn display_packages(config: &Config, packages: &[PackageOption]) -> Result<(), AppError> {
+    match config.output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(packages)?),
+        OutputFormat::Table => {
+            let mut table = Table::new();
+            table.add_row(Row::new(["package", "package_id", "default"].iter().map(|h| Cell::new(h)).collect()));
+            for package in packages {
+                table.add_row(Row::new(vec![
+                    Cell::new(&package.name),
+                    Cell::new(&package.id),
+                    Cell::new(if package.selected { "*" } else { "" }),
+                ]));
+            }
+            table.printstd();
+        },
+        OutputFormat::Csv => {
+            println!("package,package_id,default");
+            for package in packages {
+                println!("{}", csv_line([package.name.clone(), package.id.clone(), package.selected.to_string()]));
+            }
+        },
+        OutputFormat::Text => {
+            for package in packages {
+                println!("{}{}", package.name, if package.selected { " (default)" } else { "" });
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Prints each target's results followed by the cross-target leaderboard.
+/// JSON output is a single object keyed by target.
+pub fn display_batch(config: &Config, batch: &BatchReport) -> Result<(), AppError> {
+    if config.output_format == OutputFormat::Json {
+        let targets: serde_json::Map<String, serde_json::Value> = batch.reports.iter()
+            .map(|r| (r.target.clone(), results_json(
+                &r.top_dependents,
+                r.crawl.total_repos_count,
+                r.crawl.matched_count,
+                r.crawl.unique_upstreams,
+                r.elapsed_secs,
+            )))
+            .collect();
+        let errors: serde_json::Map<String, serde_json::Value> = batch.errors.iter()
+            .map(|(target, error)| (target.clone(), serde_json::Value::from(error.to_string())))
+            .collect();
+        let result = serde_json::json!({
+            "targets": targets,
+            "summary": batch.summary,
+            "leaderboard": batch.leaderboard,
+            "errors": errors,
+        });
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    if config.output_format == OutputFormat::Csv {
+        let rows = batch.reports.iter().map(|r| (r.target.clone(), &r.config, &r.top_dependents[..]));
+        print_csv_groups("target", rows);
+        return Ok(());
+    }
+
+    for report in &batch.reports {
+        println!("\n== {} ==", report.target);
+        display_results(
+            &report.config,
+            &report.top_dependents,
+            report.crawl.total_repos_count,
+            report.crawl.matched_count,
+            report.crawl.max_deps,
+            report.crawl.unique_upstreams,
+            report.elapsed_secs,
+        )?;
+    }
+
+    for (target, error) in &batch.errors {
+        println!("\n== {} ==", target);
+        println!("{}", error);
+    }
+
+    println!("\n== Summary ==");
+    if config.output_format == OutputFormat::Table {
+        let mut table = Table::new();
+        table.add_row(Row::new(["target", "dependents", "collected", "external", "shared"].iter()
+            .map(|h| Cell::new(h)).collect()));
+        for summary in &batch.summary {
+            table.add_row(Row::new(vec![
+                Cell::new(&summary.target),
+                Cell::new(&summary.dependents.to_string()),
+                Cell::new(&summary.collected.to_string()),
+                Cell::new(&summary.external.to_string()),
+                Cell::new(&summary.shared.to_string()),
+            ]));
+        }
+        table.printstd();
+    } else {
+        for summary in &batch.summary {
+            println!("{}: {} dependents, {} collected, {} external, {} shared",
+                summary.target,
+                summary.dependents,
+                summary.collected,
+                summary.external,
+                summary.shared);
+        }
+    }
+
+    println!("\n== Dependents by number of targets ==");
+    if batch.leaderboard.is_empty() {
+        println!("No dependents used by at least the minimum number of targets.");
+    } else if config.output_format == OutputFormat::Table {
+        let mut table = Table::new();
+        table.add_row(Row::new(["url", "stars", "count", "targets"].iter().map(|h| Cell::new(h)).collect()));
+        for entry in &batch.leaderboard {
+            table.add_row(Row::new(vec![
+                Cell::new(&format!("{}/{}", config.base_url, entry.repo)),
+                Cell::new(&entry.stars),
+                Cell::new(&entry.targets.len().to_string()),
+                Cell::new(&entry.targets.join(", ")),
+            ]));
+        }
+        table.printstd();
+    } else {
+        for (idx, entry) in batch.leaderboard.iter().enumerate() {
+            println!("{}. {} (⭐ {}) uses {}: {}",
+                idx + 1,
+                entry.repo,
+                entry.stars,
+                entry.targets.len(),
+                entry.targets.join(", "));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn display_diff(config: &Config, diff: &SnapshotDiff) -> Result<(), AppError> {
+    match config.output_format {
+        OutputFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(diff)?);
+        },
+        OutputFormat::Csv => {
+            println!("change,repo,from,to,delta");
+            for dep in &diff.added {
+                println!("{}", csv_line(["added".to_string(), dep.repo.clone(), String::new(), dep.stars.clone(), String::new()]));
+            }
+            for dep in &diff.removed {
+                println!("{}", csv_line(["removed".to_string(), dep.repo.clone(), dep.stars.clone(), String::new(), String::new()]));
+            }
+            for change in &diff.star_changes {
+                println!("{}", csv_line([
+                    "stars".to_string(),
+                    change.repo.clone(),
+                    change.from.clone(),
+                    change.to.clone(),
+                    format!("{:+}", change.delta),
+                ]));
+            }
+        },
+        OutputFormat::Table => {
+            println!("Changes from {} to {}", format_timestamp(diff.from), format_timestamp(diff.to));
+            for (title, deps) in [("Added", &diff.added), ("Removed", &diff.removed)] {
+                println!("\n{} dependents: {}", title, deps.len());
+                if !deps.is_empty() {
+                    let mut table = Table::new();
+                    table.add_row(Row::new(vec![Cell::new("url"), Cell::new("stars")]));
+                    for dep in deps.iter() {
+                        table.add_row(Row::new(vec![
+                            Cell::new(&format!("{}/{}", config.base_url, dep.repo)),
+                            Cell::new(&dep.stars),
+                        ]));
+                    }
+                    table.printstd();
+                }
+            }
+            println!("\nStar changes: {}", diff.star_changes.len());
+            if !diff.star_changes.is_empty() {
+                let mut table = Table::new();
+                table.add_row(Row::new(["url", "from", "to", "change"].iter().map(|h| Cell::new(h)).collect()));
+                for change in &diff.star_changes {
+                    table.add_row(Row::new(vec![
+                        Cell::new(&format!("{}/{}", config.base_url, change.repo)),
+                        Cell::new(&change.from),
+                        Cell::new(&change.to),
+                        Cell::new(&format!("{:+}", change.delta)),
+                    ]));
+                }
+                table.printstd();
+            }
+        },
+        OutputFormat::Text => {
+            println!("Changes from {} to {}", format_timestamp(diff.from), format_timestamp(diff.to));
+            for dep in &diff.added {
+                println!("+ {} (⭐ {})", dep.repo, dep.stars);
+            }
+            for dep in &diff.removed {
+                println!("- {} (⭐ {})", dep.repo, dep.stars);
+            }
+            for change in &diff.star_changes {
+                println!("~ {} (⭐ {} -> {}, {:+})", change.repo, change.from, change.to, change.delta);
+            }
+            println!("\n{} added, {} removed, {} star changes", 
+                diff.added.len(), 
+                diff.removed.len(), 
+                diff.star_changes.len());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn display_trend(format: TrendFormat, points: &[TrendPoint]) -> Result<(), AppError> {
+    let repositories: Vec<Option<usize>> = points.iter().map(|p| p.repositories).collect();
+    let packages: Vec<Option<usize>> = points.iter().map(|p| p.packages).collect();
+
+    match format {
+        TrendFormat::Table => {
+            let mut table = Table::new();
+            table.add_row(Row::new(["date", "repositories", "change", "packages", "change"].iter()
+                .map(|h| Cell::new(h)).collect()));
+            for idx in 0..points.len() {
+                table.add_row(Row::new(vec![
+                    Cell::new(&format_timestamp(points[idx].taken_at)),
+                    Cell::new(&optional_count(repositories[idx])),
+                    Cell::new(&count_change(&repositories[..=idx])),
+                    Cell::new(&optional_count(packages[idx])),
+                    Cell::new(&count_change(&packages[..=idx])),
+                ]));
+            }
+            table.printstd();
+        },
+        TrendFormat::Chart => {
+            println!("{} to {} ({} runs)", 
+                format_timestamp(points[0].taken_at), 
+                format_timestamp(points[points.len() - 1].taken_at), 
+                points.len());
+            for (label, series) in [("repositories", &repositories), ("packages", &packages)] {
+                let present: Vec<usize> = series.iter().flatten().copied().collect();
+                if let (Some(first), Some(last)) = (present.first(), present.last()) {
+                    println!("{:<12} {} {} -> {} ({:+})", 
+                        label, 
+                        sparkline(series), 
+                        first, 
+                        last, 
+                        *last as i64 - *first as i64);
+                }
+            }
+        },
+        TrendFormat::Csv => {
+            println!("timestamp,repositories,packages");
+            for point in points {
+                println!("{},{},{}", 
+                    format_timestamp(point.taken_at), 
+                    optional_count(point.repositories), 
+                    optional_count(point.packages));
+            }
+        },
+    }
+
+    Ok(())
+}
+
+fn optional_count(count: Option<usize>) -> String {
+    count.map(|count| count.to_string()).unwrap_or_default()
+}
+
+/// Change of the last value of `series` since the previous recorded value.
+fn count_change(series: &[Option<usize>]) -> String {
+    let Some((Some(last), earlier)) = series.split_last() else {
+        return String::new();
+    };
+    match earlier.iter().rev().flatten().next() {
+        Some(previous) => format!("{:+}", *last as i64 - *previous as i64),
+        None => String::new(),
+    }
+}
+
+fn table_columns(config: &Config) -> Vec<Column> {
+    if let Some(columns) = &config.columns {
+        return columns.clone();
+    }
+
+    let mut columns = vec![Column::Url, Column::Stars];
+    if config.merge_types {
+        columns.insert(1, Column::Type);
+    }
+    if config.collapse_forks {
+        columns.push(Column::Collapsed);
+    }
+    if config.fetch_metadata {
+        columns.extend([Column::Language, Column::License, Column::LastCommit]);
+    }
+    if config.show_desc {
+        columns.push(Column::Description);
+    }
+    columns
+}
+
+/// The requested columns, or every column.
+fn csv_columns(config: &Config) -> Vec<Column> {
+    match &config.columns {
+        Some(columns) => columns.clone(),
+        None => Column::value_variants().to_vec(),
+    }
+}
+
+/// One CSV table of several groups of dependents, with the group name in a
+/// leading `label` column.
+fn print_csv_groups<'a, I>(label: &str, groups: I)
+where
+    I: IntoIterator<Item = (String, &'a Config, &'a [Dependent])>,
+{
+    let mut header_printed = false;
+    for (name, config, dependents) in groups {
+        let columns = csv_columns(config);
+        if !header_printed {
+            let names = columns.iter().map(|c| c.name().to_string());
+            println!("{}", csv_line(std::iter::once(label.to_string()).chain(names)));
+            header_printed = true;
+        }
+        for (idx, dep) in dependents.iter().enumerate() {
+            let values = columns.iter().map(|c| c.value(&config.base_url, idx + 1, dep));
+            println!("{}", csv_line(std::iter::once(name.clone()).chain(values)));
+        }
+    }
+}
+
+/// Joins values into a CSV record, quoting those containing commas, quotes
+/// or line breaks.
+pub fn csv_line<I: IntoIterator<Item = String>>(values: I) -> String {
+    values.into_iter()
+        .map(|value| {
+            if value.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn text_line(config: &Config, rank: usize, dep: &Dependent) -> String {
+    if let Some(template) = &config.template {
+        return template.render(&config.base_url, rank, dep);
+    }
+
+    if let Some(columns) = &config.columns {
+        let values: Vec<String> = columns.iter().map(|c| c.value(&config.base_url, rank, dep)).collect();
+        return values.join("  ");
+    }
+
+    let desc_text = match &dep.description {
+        Some(desc) if config.show_desc && !desc.is_empty() => format!("\n   {}", desc),
+        _ => String::new(),
+    };
+    let forks_text = match dep.collapsed_forks {
+        0 => String::new(),
+        1 => " +1 fork".to_string(),
+        count => format!(" +{} forks", count),
+    };
+    format!("{}. {} (⭐ {}){}{}", rank, dep.repo, dep.stars, forks_text, desc_text)
+}
@@ -0,0 +1,111 @@
+use std::io::IsTerminal;
+use std::time::{Duration, Instant};
+use clap::ValueEnum;
+use console::Term;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+const PLAIN_REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProgressMode {
+    /// Draw a progress bar only when stderr is a terminal
+    Auto,
+    /// Always draw a progress bar
+    Always,
+    /// Never report progress
+    Never,
+    /// Print periodic status lines, suited to CI logs
+    Plain,
+}
+
+impl ProgressMode {
+    /// Resolves `Auto` against the current stderr; other modes are returned unchanged.
+    pub fn resolve(self) -> ProgressMode {
+        match self {
+            ProgressMode::Auto if std::io::stderr().is_terminal() => ProgressMode::Always,
+            ProgressMode::Auto => ProgressMode::Never,
+            mode => mode,
+        }
+    }
+}
+
+/// Reports crawl progress as dependents are collected, either as an
+/// interactive bar, periodic status lines, or not at all.
+pub struct Progress {
+    bar: Option<ProgressBar>,
+    plain: bool,
+    total: u64,
+    position: u64,
+    last_report: Instant,
+    last_line: Option<String>,
+}
+
+impl Progress {
+    pub fn new(mode: ProgressMode, total: u64) -> Self {
+        let mode = mode.resolve();
+        let bar = if mode == ProgressMode::Always {
+            let target = ProgressDrawTarget::term_like(Box::new(Term::stderr()));
+            let bar = if total > 0 {
+                let bar = ProgressBar::with_draw_target(Some(total), target);
+                bar.set_style(ProgressStyle::default_bar()
+                    .template("{spinner:.green} [{elapsed_precise}] [{bar:50.cyan/blue}] {percent}% ({pos}/{len}) [{eta}]")
+                    .unwrap()
+                    .progress_chars("█▉▊▋▌▍▎▏ "));
+                bar
+            } else {
+                let bar = ProgressBar::with_draw_target(None, target);
+                bar.set_style(ProgressStyle::default_spinner()
+                    .template("{spinner:.green} [{elapsed_precise}] {pos} dependents")
+                    .unwrap());
+                bar
+            };
+            Some(bar)
+        } else {
+            None
+        };
+
+        Progress {
+            bar,
+            plain: mode == ProgressMode::Plain,
+            total,
+            position: 0,
+            last_report: Instant::now(),
+            last_line: None,
+        }
+    }
+
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position;
+
+        if let Some(bar) = &self.bar {
+            bar.set_position(position);
+        } else if self.plain && self.last_report.elapsed() >= PLAIN_REPORT_INTERVAL {
+            self.report();
+            self.last_report = Instant::now();
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if let Some(bar) = &self.bar {
+            bar.finish_with_message("Download complete");
+        } else if self.plain {
+            self.report();
+        }
+    }
+
+    /// The last status line printed in plain mode.
+    pub fn last_line(&self) -> Option<&str> {
+        self.last_line.as_deref()
+    }
+
+    fn report(&mut self) {
+        let line = if let Some(percent) = (self.position * 100).checked_div(self.total) {
+            let percent = std::cmp::min(percent, 100);
+            format!("Progress: {}/{} dependents ({}%)", self.position, self.total, percent)
+        } else {
+            format!("Progress: {} dependents", self.position)
+        };
+        eprintln!("{}", line);
+        self.last_line = Some(line);
+    }
+}
@@ -0,0 +1,255 @@
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use scraper::Selector;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// The CSS selectors used to read GitHub pages. Keys left out of a
+/// selectors file keep their built-in value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SelectorProfile {
+    /// A dependent on a dependents page
+    pub dependent_row: String,
+    /// The repository link within a dependent
+    pub dependent_link: String,
+    /// The star count within a dependent
+    pub stars: String,
+    /// The icon next to the fork count within a dependent
+    pub forks_icon: String,
+    /// The link to the next dependents page
+    pub next_page: String,
+    /// The "N Repositories" and "N Packages" links of a dependents page
+    pub header_counts: String,
+    /// A package of the package dropdown
+    pub package_item: String,
+    /// The name within a package of the dropdown
+    pub package_text: String,
+    /// The description on a repository page
+    pub description: String,
+    /// A topic of a repository page
+    pub topic: String,
+    /// The icon next to the license on a repository page
+    pub license_icon: String,
+    /// The icon next to the homepage on a repository page
+    pub homepage_icon: String,
+    /// The link next to the homepage icon
+    pub homepage_link: String,
+    /// The marked up language of older repository pages
+    pub language: String,
+    /// A section of the repository page sidebar
+    pub sidebar_cell: String,
+    /// The heading of a sidebar section
+    pub sidebar_heading: String,
+    /// A language of the "Languages" sidebar section
+    pub sidebar_language: String,
+    /// The notices that may say a repository is archived
+    pub archived_notice: String,
+    /// The time of the last commit on a repository page
+    pub last_commit: String,
+    /// The links that may follow "forked from" or "mirrored from"
+    pub fork_link: String,
+    /// The title of any page
+    pub page_title: String,
+    /// The login form GitHub shows instead of a page
+    pub login_form: String,
+    /// The notice of a dependents page without dependents
+    pub blankslate: String,
+}
+
+impl Default for SelectorProfile {
+    fn default() -> Self {
+        SelectorProfile {
+            dependent_row: ".flex-items-center".to_string(),
+            dependent_link: "a.text-bold".to_string(),
+            stars: "div span".to_string(),
+            forks_icon: "svg.octicon-repo-forked".to_string(),
+            next_page: ".paginate-container a".to_string(),
+            header_counts: ".table-list-header-toggle .btn-link".to_string(),
+            package_item: "a.select-menu-item".to_string(),
+            package_text: ".select-menu-item-text".to_string(),
+            description: "div.BorderGrid-cell p".to_string(),
+            topic: "a.topic-tag".to_string(),
+            license_icon: "svg.octicon-law".to_string(),
+            homepage_icon: "svg.octicon-link".to_string(),
+            homepage_link: "a[href]".to_string(),
+            language: "[itemprop='programmingLanguage']".to_string(),
+            sidebar_cell: "div.BorderGrid-cell".to_string(),
+            sidebar_heading: "h2".to_string(),
+            sidebar_language: "span.text-bold".to_string(),
+            archived_notice: ".flash-warn, span.Label".to_string(),
+            last_commit: "relative-time[datetime]".to_string(),
+            fork_link: "a".to_string(),
+            page_title: "title".to_string(),
+            login_form: "form[action='/session'], input[name='login']".to_string(),
+            blankslate: ".blankslate".to_string(),
+        }
+    }
+}
+
+impl SelectorProfile {
+    /// The selectors as (key, selector), in file order.
+    pub fn entries(&self) -> [(&'static str, &str); 23] {
+        [
+            ("dependent-row", &self.dependent_row),
+            ("dependent-link", &self.dependent_link),
+            ("stars", &self.stars),
+            ("forks-icon", &self.forks_icon),
+            ("next-page", &self.next_page),
+            ("header-counts", &self.header_counts),
+            ("package-item", &self.package_item),
+            ("package-text", &self.package_text),
+            ("description", &self.description),
+            ("topic", &self.topic),
+            ("license-icon", &self.license_icon),
+            ("homepage-icon", &self.homepage_icon),
+            ("homepage-link", &self.homepage_link),
+            ("language", &self.language),
+            ("sidebar-cell", &self.sidebar_cell),
+            ("sidebar-heading", &self.sidebar_heading),
+            ("sidebar-language", &self.sidebar_language),
+            ("archived-notice", &self.archived_notice),
+            ("last-commit", &self.last_commit),
+            ("fork-link", &self.fork_link),
+            ("page-title", &self.page_title),
+            ("login-form", &self.login_form),
+            ("blankslate", &self.blankslate),
+        ]
+    }
+
+    pub fn parse(content: &str, source: &str) -> Result<SelectorProfile, AppError> {
+        toml::from_str(content).map_err(|e| AppError::Other(format!("{}: {}", source, e)))
+    }
+
+    pub fn to_toml(&self) -> String {
+        // A struct of strings always serializes
+        toml::to_string(self).unwrap_or_default()
+    }
+}
+
+/// A validated [`SelectorProfile`].
+#[derive(Debug, Clone)]
+pub struct Selectors {
+    pub profile: SelectorProfile,
+    pub dependent_row: Selector,
+    pub dependent_link: Selector,
+    pub stars: Selector,
+    pub forks_icon: Selector,
+    pub next_page: Selector,
+    pub header_counts: Selector,
+    pub package_item: Selector,
+    pub package_text: Selector,
+    pub description: Selector,
+    pub topic: Selector,
+    pub license_icon: Selector,
+    pub homepage_icon: Selector,
+    pub homepage_link: Selector,
+    pub language: Selector,
+    pub sidebar_cell: Selector,
+    pub sidebar_heading: Selector,
+    pub sidebar_language: Selector,
+    pub archived_notice: Selector,
+    pub last_commit: Selector,
+    pub fork_link: Selector,
+    pub page_title: Selector,
+    pub login_form: Selector,
+    pub blankslate: Selector,
+}
+
+static SELECTORS: OnceLock<Selectors> = OnceLock::new();
+
+/// The selectors pages are read with: the installed ones, or the built-in
+/// profile when none were installed.
+pub fn selectors() -> &'static Selectors {
+    SELECTORS.get_or_init(|| {
+        Selectors::compile(SelectorProfile::default()).expect("built-in selectors are valid")
+    })
+}
+
+/// Makes `selectors` the ones every page of this process is read with. Fails
+/// once a page has been read with other selectors.
+pub fn install_selectors(selectors: Selectors) -> Result<(), AppError> {
+    SELECTORS.set(selectors)
+        .map_err(|_| AppError::Other("Selectors are already in use".to_string()))
+}
+
+impl Selectors {
+    /// Compiles every selector of `profile`, naming the first invalid one.
+    pub fn compile(profile: SelectorProfile) -> Result<Selectors, AppError> {
+        let compile = |key: &str, selector: &str| Selector::parse(selector)
+            .map_err(|_| AppError::Other(format!("Invalid selector for {}: '{}'", key, selector)));
+
+        Ok(Selectors {
+            dependent_row: compile("dependent-row", &profile.dependent_row)?,
+            dependent_link: compile("dependent-link", &profile.dependent_link)?,
+            stars: compile("stars", &profile.stars)?,
+            forks_icon: compile("forks-icon", &profile.forks_icon)?,
+            next_page: compile("next-page", &profile.next_page)?,
+            header_counts: compile("header-counts", &profile.header_counts)?,
+            package_item: compile("package-item", &profile.package_item)?,
+            package_text: compile("package-text", &profile.package_text)?,
+            description: compile("description", &profile.description)?,
+            topic: compile("topic", &profile.topic)?,
+            license_icon: compile("license-icon", &profile.license_icon)?,
+            homepage_icon: compile("homepage-icon", &profile.homepage_icon)?,
+            homepage_link: compile("homepage-link", &profile.homepage_link)?,
+            language: compile("language", &profile.language)?,
+            sidebar_cell: compile("sidebar-cell", &profile.sidebar_cell)?,
+            sidebar_heading: compile("sidebar-heading", &profile.sidebar_heading)?,
+            sidebar_language: compile("sidebar-language", &profile.sidebar_language)?,
+            archived_notice: compile("archived-notice", &profile.archived_notice)?,
+            last_commit: compile("last-commit", &profile.last_commit)?,
+            fork_link: compile("fork-link", &profile.fork_link)?,
+            page_title: compile("page-title", &profile.page_title)?,
+            login_form: compile("login-form", &profile.login_form)?,
+            blankslate: compile("blankslate", &profile.blankslate)?,
+            profile,
+        })
+    }
+
+    /// Loads a selectors file overriding the built-in profile.
+    pub fn load(path: &Path) -> Result<Selectors, AppError> {
+        let source = path.display().to_string();
+        let content = fs::read_to_string(path)
+            .map_err(|e| AppError::Other(format!("Could not read {}: {}", source, e)))?;
+        Selectors::compile(SelectorProfile::parse(&content, &source)?)
+            .map_err(|e| match e {
+                AppError::Other(message) => AppError::Other(format!("{}: {}", source, message)),
+                e => e,
+            })
+    }
+
+    /// The compiled selectors as (key, selector, compiled), in file order.
+    pub fn entries(&self) -> Vec<(&'static str, &str, &Selector)> {
+        let compiled = [
+            &self.dependent_row,
+            &self.dependent_link,
+            &self.stars,
+            &self.forks_icon,
+            &self.next_page,
+            &self.header_counts,
+            &self.package_item,
+            &self.package_text,
+            &self.description,
+            &self.topic,
+            &self.license_icon,
+            &self.homepage_icon,
+            &self.homepage_link,
+            &self.language,
+            &self.sidebar_cell,
+            &self.sidebar_heading,
+            &self.sidebar_language,
+            &self.archived_notice,
+            &self.last_commit,
+            &self.fork_link,
+            &self.page_title,
+            &self.login_form,
+            &self.blankslate,
+        ];
+        self.profile.entries().into_iter().zip(compiled)
+            .map(|((key, selector), compiled)| (key, selector, compiled))
+            .collect()
+    }
+}
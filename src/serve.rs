@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use futures::future::{BoxFuture, FutureExt, Shared};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use reqwest::Url;
+use serde_json::json;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+use crate::config::Config;
+use crate::dependent::{convert_stars_to_number, DependentType, TypeSelection};
+use crate::error::AppError;
+use crate::github::{Crawl, Crawler};
+use crate::metrics::metrics_response;
+use crate::output::results_json;
+use crate::progress::ProgressMode;
+
+pub struct ServeOptions {
+    pub bind: SocketAddr,
+    /// Cached results older than this are refreshed in the background
+    pub refresh_after: Duration,
+}
+
+/// A repository as `owner/repo` and the dependents list to crawl.
+type CrawlKey = (String, DependentType);
+type CrawlResult = Result<Arc<Crawl>, Arc<AppError>>;
+
+struct CachedCrawl {
+    crawl: Arc<Crawl>,
+    fetched_at: Instant,
+}
+
+/// Serves crawls from memory, crawling each repository once however many
+/// requests ask for it at the same time. Results older than `refresh_after`
+/// are still served while a background task re-crawls them.
+pub struct DependentsService {
+    crawler: Crawler,
+    config: Config,
+    refresh_after: Duration,
+    results: Mutex<HashMap<CrawlKey, CachedCrawl>>,
+    in_flight: Mutex<HashMap<CrawlKey, Shared<BoxFuture<'static, CrawlResult>>>>,
+    queued: Mutex<HashSet<CrawlKey>>,
+    refresh_queue: UnboundedSender<CrawlKey>,
+}
+
+impl DependentsService {
+    /// Creates the service and the receiving end of its refresh queue, to be
+    /// drained with [`DependentsService::run_refresh_queue`].
+    pub fn new(crawler: Crawler, config: Config, refresh_after: Duration) -> (Arc<Self>, UnboundedReceiver<CrawlKey>) {
+        let (refresh_queue, receiver) = unbounded_channel();
+        let service = Arc::new(DependentsService {
+            crawler,
+            config,
+            refresh_after,
+            results: Mutex::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+            queued: Mutex::new(HashSet::new()),
+            refresh_queue,
+        });
+        (service, receiver)
+    }
+
+    /// Re-crawls queued repositories one at a time, bypassing the response cache.
+    pub async fn run_refresh_queue(self: Arc<Self>, mut receiver: UnboundedReceiver<CrawlKey>) {
+        while let Some(key) = receiver.recv().await {
+            if let Err(e) = self.crawl(key.clone(), false).await {
+                eprintln!("Error refreshing {}: {}", key.0, e);
+            }
+            self.queued.lock().unwrap().remove(&key);
+        }
+    }
+
+    /// The latest crawl of `key`, crawling it first if there is none.
+    pub async fn dependents(self: &Arc<Self>, key: CrawlKey) -> CrawlResult {
+        let cached = self.results.lock().unwrap().get(&key)
+            .map(|cached| (cached.crawl.clone(), cached.fetched_at.elapsed()));
+        match cached {
+            Some((crawl, age)) => {
+                if age >= self.refresh_after && self.queued.lock().unwrap().insert(key.clone()) {
+                    let _ = self.refresh_queue.send(key);
+                }
+                Ok(crawl)
+            },
+            None => self.crawl(key, true).await,
+        }
+    }
+
+    /// Crawls `key`, joining the crawl already running for it if any.
+    async fn crawl(self: &Arc<Self>, key: CrawlKey, use_cache: bool) -> CrawlResult {
+        let shared = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(&key) {
+                Some(shared) => shared.clone(),
+                None => {
+                    let service = self.clone();
+                    let crawl_key = key.clone();
+                    let shared = async move {
+                        let result = service.run_crawl(&crawl_key, use_cache).await;
+                        if let Ok(crawl) = &result {
+                            service.results.lock().unwrap().insert(crawl_key.clone(), CachedCrawl {
+                                crawl: crawl.clone(),
+                                fetched_at: Instant::now(),
+                            });
+                        }
+                        service.in_flight.lock().unwrap().remove(&crawl_key);
+                        result
+                    }.boxed().shared();
+                    in_flight.insert(key, shared.clone());
+                    shared
+                },
+            }
+        };
+        shared.await
+    }
+
+    async fn run_crawl(&self, (repo, dependent_type): &CrawlKey, use_cache: bool) -> CrawlResult {
+        let config = Config {
+            is_package: *dependent_type == DependentType::Package,
+            type_selection: match dependent_type {
+                DependentType::Repository => TypeSelection::Repositories,
+                DependentType::Package => TypeSelection::Packages,
+            },
+            use_cache: use_cache && self.config.use_cache,
+            // Every dependent is kept so requests can ask for fewer stars than the default
+            min_stars: 0.0,
+            quiet: true,
+            progress: ProgressMode::Never,
+            ..self.config.with_target(repo)?
+        };
+        self.crawler.crawl(&config).await
+            .map(Arc::new)
+            .map_err(Arc::new)
+    }
+
+    /// Answers a request, returning the status and the JSON body.
+    pub async fn handle(self: &Arc<Self>, method: &Method, path_and_query: &str) -> (StatusCode, serde_json::Value) {
+        let start_time = Instant::now();
+        let error = |status: StatusCode, message: String| (status, json!({ "error": message }));
+
+        let Ok(url) = Url::parse(&format!("http://localhost{}", path_and_query)) else {
+            return error(StatusCode::BAD_REQUEST, "Invalid request path".to_string());
+        };
+        let segments: Vec<&str> = url.path().trim_matches('/').split('/').collect();
+        let ["v1", owner, repo, "dependents"] = segments.as_slice() else {
+            return error(StatusCode::NOT_FOUND, format!("No route for {}", url.path()));
+        };
+        if method != Method::GET {
+            return error(StatusCode::METHOD_NOT_ALLOWED, format!("{} is not supported", method));
+        }
+
+        let mut dependent_type = DependentType::Repository;
+        let mut min_stars = self.config.min_stars;
+        let mut limit = self.config.top_n;
+        for (name, value) in url.query_pairs() {
+            match name.as_ref() {
+                "type" => dependent_type = match value.as_ref() {
+                    "repository" | "repositories" => DependentType::Repository,
+                    "package" | "packages" => DependentType::Package,
+                    _ => return error(StatusCode::BAD_REQUEST, format!(
+                        "Invalid type '{}'. Expected repositories or packages", value
+                    )),
+                },
+                "min_stars" => match value.parse::<f64>() {
+                    Ok(stars) if stars.is_finite() && stars >= 0.0 => min_stars = stars,
+                    _ => return error(StatusCode::BAD_REQUEST, format!(
+                        "Invalid min_stars '{}': must be a non-negative number", value
+                    )),
+                },
+                "limit" => match value.parse::<usize>() {
+                    Ok(rows) if rows > 0 => limit = rows,
+                    _ => return error(StatusCode::BAD_REQUEST, format!(
+                        "Invalid limit '{}': must be a positive integer", value
+                    )),
+                },
+                _ => {},
+            }
+        }
+
+        let key = (format!("{}/{}", owner, repo).to_lowercase(), dependent_type);
+        match self.dependents(key).await {
+            Ok(crawl) => {
+                let matching: Vec<_> = crawl.dependents.iter()
+                    .filter(|dep| convert_stars_to_number(&dep.stars) >= min_stars)
+                    .cloned()
+                    .collect();
+                let top: Vec<_> = matching.iter().take(limit).cloned().collect();
+                // Collapsed entries stand for themselves and the forks folded into them
+                let matched_count = matching.iter().map(|dep| 1 + dep.collapsed_forks).sum();
+                let unique_upstreams = crawl.unique_upstreams.map(|_| matching.len());
+                let body = results_json(
+                    &top,
+                    crawl.total_repos_count,
+                    matched_count,
+                    unique_upstreams,
+                    start_time.elapsed().as_secs_f64(),
+                );
+                (StatusCode::OK, body)
+            },
+            Err(e) => {
+                let status = match e.as_ref() {
+                    AppError::NotFound { .. } => StatusCode::NOT_FOUND,
+                    AppError::RateLimited { .. } => StatusCode::SERVICE_UNAVAILABLE,
+                    AppError::InvalidRepoSpec(_) => StatusCode::BAD_REQUEST,
+                    _ => StatusCode::BAD_GATEWAY,
+                };
+                error(status, e.to_string())
+            },
+        }
+    }
+}
+
+async fn respond(service: Arc<DependentsService>, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.uri().path() == "/metrics" {
+        return Ok(metrics_response());
+    }
+    let path_and_query = request.uri().path_and_query().map_or("/", |pq| pq.as_str()).to_string();
+    let (status, body) = service.handle(request.method(), &path_and_query).await;
+    let response = Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap_or_default();
+    Ok(response)
+}
+
+/// Serves `/v1/{owner}/{repo}/dependents` and `/metrics` until interrupted.
+pub async fn run_serve(config: &Config, options: &ServeOptions) -> Result<(), AppError> {
+    let crawler = Crawler::new(&config.client_options()?)?;
+    let (service, refresh_queue) = DependentsService::new(crawler, config.clone(), options.refresh_after);
+    tokio::spawn(service.clone().run_refresh_queue(refresh_queue));
+
+    let make_service = make_service_fn(move |_| {
+        let service = service.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| respond(service.clone(), request)))
+        }
+    });
+    let server = Server::try_bind(&options.bind)
+        .map_err(|e| AppError::Other(format!("Could not listen on {}: {}", options.bind, e)))?
+        .serve(make_service);
+    if !config.quiet {
+        eprintln!("Serving dependents on http://{}/v1/{{owner}}/{{repo}}/dependents", options.bind);
+    }
+    server.await.map_err(|e| AppError::Other(format!("Server error: {}", e)))
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use dirs::home_dir;
+use serde::Deserialize;
+
+use crate::cli::GlobalArgs;
+use crate::error::AppError;
+use crate::output::OutputFormat;
+
+pub const PROJECT_CONFIG_FILE: &str = ".ghtopdep.toml";
+const ENV_PREFIX: &str = "GHTOPDEP_";
+
+/// Defaults that can be set in config files, profiles and `GHTOPDEP_*`
+/// environment variables. Unset values fall through to the next layer.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+    pub format: Option<OutputFormat>,
+    pub rows: Option<usize>,
+    pub minstar: Option<f64>,
+    /// Cache expiry in hours
+    pub cache_ttl: Option<u64>,
+    /// Shell command printing a GitHub token on stdout
+    pub token_command: Option<String>,
+    pub base_url: Option<String>,
+    /// TOML file overriding the built-in CSS selectors
+    pub selectors: Option<PathBuf>,
+    /// Proxy URL, overriding `HTTPS_PROXY` and `HTTP_PROXY`
+    pub proxy: Option<String>,
+    /// PEM bundle of extra root certificates
+    pub ca_cert: Option<PathBuf>,
+    /// PEM client certificate, with its key unless `client-key` is set
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+    /// Connect timeout in seconds
+    pub connect_timeout: Option<u64>,
+    /// Request timeout in seconds
+    pub timeout: Option<u64>,
+    pub user_agent: Option<String>,
+}
+
+/// A `config.toml` or `.ghtopdep.toml` file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    #[serde(default)]
+    pub defaults: Settings,
+    #[serde(default)]
+    pub profiles: HashMap<String, Settings>,
+}
+
+impl Settings {
+    /// Fills every unset value from `fallback`.
+    pub fn or(self, fallback: Settings) -> Settings {
+        Settings {
+            format: self.format.or(fallback.format),
+            rows: self.rows.or(fallback.rows),
+            minstar: self.minstar.or(fallback.minstar),
+            cache_ttl: self.cache_ttl.or(fallback.cache_ttl),
+            token_command: self.token_command.or(fallback.token_command),
+            base_url: self.base_url.or(fallback.base_url),
+            selectors: self.selectors.or(fallback.selectors),
+            proxy: self.proxy.or(fallback.proxy),
+            ca_cert: self.ca_cert.or(fallback.ca_cert),
+            client_cert: self.client_cert.or(fallback.client_cert),
+            client_key: self.client_key.or(fallback.client_key),
+            connect_timeout: self.connect_timeout.or(fallback.connect_timeout),
+            timeout: self.timeout.or(fallback.timeout),
+            user_agent: self.user_agent.or(fallback.user_agent),
+        }
+    }
+
+    /// Settings given as global command line flags.
+    pub fn from_flags(global: &GlobalArgs) -> Result<Settings, AppError> {
+        let settings = Settings {
+            cache_ttl: global.cache_ttl,
+            token_command: global.token_command.clone(),
+            base_url: global.base_url.clone(),
+            selectors: global.selectors.clone(),
+            proxy: global.proxy.clone(),
+            ca_cert: global.ca_cert.clone(),
+            client_cert: global.client_cert.clone(),
+            client_key: global.client_key.clone(),
+            connect_timeout: global.connect_timeout,
+            timeout: global.timeout,
+            user_agent: global.user_agent.clone(),
+            ..Settings::default()
+        };
+        settings.validate("flags")?;
+        Ok(settings)
+    }
+
+    /// Reads `GHTOPDEP_<KEY>` for every settings key, e.g. `GHTOPDEP_ROWS`
+    /// or `GHTOPDEP_CA_CERT`.
+    pub fn from_env_vars<I>(vars: I) -> Result<Settings, AppError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut table = toml::Table::new();
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else { continue };
+            let key = key.to_lowercase().replace('_', "-");
+            let value = match key.as_str() {
+                "rows" | "cache-ttl" | "connect-timeout" | "timeout" => toml::Value::Integer(value.trim().parse::<i64>()
+                    .map_err(|_| invalid_env(&name, &value))?),
+                "minstar" => toml::Value::Float(value.trim().parse::<f64>()
+                    .map_err(|_| invalid_env(&name, &value))?),
+                "format" | "token-command" | "base-url" | "selectors" | "proxy" | "ca-cert"
+                | "client-cert" | "client-key" | "user-agent" => toml::Value::String(value),
+                // Other GHTOPDEP_ variables, e.g. GHTOPDEP_PROFILE, are not settings
+                _ => continue,
+            };
+            table.insert(key, value);
+        }
+
+        let settings: Settings = table.try_into()
+            .map_err(|e| AppError::Other(format!("Invalid environment setting: {}", e)))?;
+        settings.validate("environment")?;
+        Ok(settings)
+    }
+
+    fn validate(&self, source: &str) -> Result<(), AppError> {
+        if self.rows == Some(0) {
+            return Err(AppError::Other(format!("{}: rows must be at least 1", source)));
+        }
+        if let Some(minstar) = self.minstar {
+            if !minstar.is_finite() || minstar < 0.0 {
+                return Err(AppError::Other(format!("{}: minstar must be a non-negative number", source)));
+            }
+        }
+        if self.connect_timeout == Some(0) || self.timeout == Some(0) {
+            return Err(AppError::Other(format!("{}: timeouts must be at least 1 second", source)));
+        }
+        if let Some(proxy) = &self.proxy {
+            if !proxy.starts_with("http://") && !proxy.starts_with("https://") {
+                return Err(AppError::Other(format!(
+                    "{}: proxy must start with http:// or https://", source
+                )));
+            }
+        }
+        if let Some(base_url) = &self.base_url {
+            if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+                return Err(AppError::Other(format!(
+                    "{}: base-url must start with http:// or https://", source
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ConfigFile {
+    pub fn parse(content: &str, source: &str) -> Result<ConfigFile, AppError> {
+        let file: ConfigFile = toml::from_str(content)
+            .map_err(|e| AppError::Other(format!("{}: {}", source, e)))?;
+
+        file.defaults.validate(source)?;
+        for (name, profile) in &file.profiles {
+            profile.validate(&format!("{} [profiles.{}]", source, name))?;
+        }
+        Ok(file)
+    }
+
+    /// Loads a config file, returning `None` when it does not exist.
+    pub fn load(path: &Path) -> Result<Option<ConfigFile>, AppError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(Some(ConfigFile::parse(&content, &path.display().to_string())?))
+    }
+
+    fn profile(&self, name: Option<&str>) -> Option<&Settings> {
+        name.and_then(|name| self.profiles.get(name))
+    }
+}
+
+pub fn user_config_path() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(".config").join("ghtopdep").join("config.toml"))
+}
+
+/// Merges settings layers, highest precedence first: `flags`, `env`, the
+/// selected profile from the project file, then from the user file, then the
+/// project file defaults and finally the user file defaults. Selecting a
+/// profile that no file defines is an error.
+pub fn resolve_settings(
+    flags: Settings,
+    env: Settings,
+    project: Option<&ConfigFile>,
+    user: Option<&ConfigFile>,
+    profile: Option<&str>,
+) -> Result<Settings, AppError> {
+    let files: Vec<&ConfigFile> = [project, user].into_iter().flatten().collect();
+    let profiles: Vec<&Settings> = files.iter().filter_map(|file| file.profile(profile)).collect();
+
+    if let Some(name) = profile {
+        if profiles.is_empty() {
+            return Err(AppError::Other(format!("Unknown profile '{}'", name)));
+        }
+    }
+
+    let layers = profiles.into_iter().chain(files.iter().map(|file| &file.defaults));
+    Ok(layers.fold(flags.or(env), |settings, layer| settings.or(layer.clone())))
+}
+
+/// Loads the user and project config files and the environment, and merges
+/// them with the global flags.
+pub fn load_settings(global: &GlobalArgs) -> Result<Settings, AppError> {
+    let user = match user_config_path() {
+        Some(path) => ConfigFile::load(&path)?,
+        None => None,
+    };
+    let project = ConfigFile::load(Path::new(PROJECT_CONFIG_FILE))?;
+    let env = Settings::from_env_vars(std::env::vars())?;
+    let profile = global.profile.clone()
+        .or_else(|| std::env::var(format!("{}PROFILE", ENV_PREFIX)).ok());
+
+    resolve_settings(
+        Settings::from_flags(global)?,
+        env,
+        project.as_ref(),
+        user.as_ref(),
+        profile.as_deref(),
+    )
+}
+
+fn invalid_env(name: &str, value: &str) -> AppError {
+    AppError::Other(format!("Invalid value '{}' for {}", value, name))
+}
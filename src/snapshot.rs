@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::get_data_dir;
+use crate::config::Config;
+use crate::dependent::{Dependent, convert_stars_to_number};
+use crate::error::AppError;
+use crate::github::Crawl;
+
+/// The dependents of one repository at a point in time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub owner: String,
+    pub repo: String,
+    /// `repository` or `package`, followed by `-<package>` when a package was selected
+    pub dependent_type: String,
+    /// Seconds since the Unix epoch
+    pub taken_at: u64,
+    /// Dependents count from the page header
+    pub total: usize,
+    pub dependents: Vec<Dependent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StarChange {
+    pub repo: String,
+    pub from: String,
+    pub to: String,
+    pub delta: f64,
+}
+
+/// The changes between two snapshots of the same repository.
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotDiff {
+    pub from: u64,
+    pub to: u64,
+    pub added: Vec<Dependent>,
+    pub removed: Vec<Dependent>,
+    pub star_changes: Vec<StarChange>,
+}
+
+impl Snapshot {
+    pub fn new(config: &Config, crawl: &Crawl, taken_at: SystemTime) -> Self {
+        Snapshot {
+            owner: config.owner.clone(),
+            repo: config.repo.clone(),
+            dependent_type: config.snapshot_kind(),
+            taken_at: taken_at.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            total: crawl.max_deps,
+            dependents: crawl.dependents.clone(),
+        }
+    }
+
+    /// Writes the snapshot below `root` as `<owner>/<repo>/<type>/<taken_at>.json`,
+    /// or `<taken_at>-<n>.json` when snapshots were saved earlier in the same second.
+    pub fn save(&self, root: &Path) -> Result<PathBuf, AppError> {
+        let dir = snapshot_dir_for(root, &self.owner, &self.repo, &self.dependent_type);
+        fs::create_dir_all(&dir)?;
+        let content = serde_json::to_string_pretty(self)?;
+        for sequence in 1.. {
+            let path = match sequence {
+                1 => dir.join(format!("{}.json", self.taken_at)),
+                n => dir.join(format!("{}-{}.json", self.taken_at, n)),
+            };
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(content.as_bytes())?;
+                    return Ok(path);
+                },
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        unreachable!("snapshot sequence numbers are unbounded")
+    }
+
+    pub fn load(path: &Path) -> Result<Snapshot, AppError> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| AppError::Other(format!("Could not read snapshot {}: {}", path.display(), e)))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// `taken_at` as an RFC 3339 timestamp.
+    pub fn taken_at_display(&self) -> String {
+        format_timestamp(self.taken_at)
+    }
+}
+
+pub fn format_timestamp(secs: u64) -> String {
+    humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(secs)).to_string()
+}
+
+pub fn default_snapshot_root() -> PathBuf {
+    get_data_dir().join("snapshots")
+}
+
+fn snapshot_dir_for(root: &Path, owner: &str, repo: &str, dependent_type: &str) -> PathBuf {
+    root.join(owner.to_lowercase()).join(repo.to_lowercase()).join(dependent_type)
+}
+
+/// Snapshot files of the configured repository and dependent type, oldest first.
+pub fn list_snapshots(root: &Path, config: &Config) -> Result<Vec<PathBuf>, AppError> {
+    let dir = snapshot_dir_for(root, &config.owner, &config.repo, &config.snapshot_kind());
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots: Vec<((u64, u64), PathBuf)> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| Some((snapshot_order(path.file_stem()?.to_str()?)?, path)))
+        .collect();
+    snapshots.sort();
+    Ok(snapshots.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Orders snapshot ids: `<taken_at>` comes before `<taken_at>-2`.
+fn snapshot_order(id: &str) -> Option<(u64, u64)> {
+    match id.split_once('-') {
+        Some((taken_at, sequence)) => Some((taken_at.parse().ok()?, sequence.parse().ok()?)),
+        None => Some((id.parse().ok()?, 1)),
+    }
+}
+
+/// Resolves a snapshot given as a file path or as the `taken_at` id of a
+/// stored snapshot of the configured repository.
+pub fn resolve_snapshot(root: &Path, config: &Config, spec: &str) -> Result<PathBuf, AppError> {
+    let path = Path::new(spec);
+    if path.is_file() {
+        return Ok(path.to_path_buf());
+    }
+
+    list_snapshots(root, config)?.into_iter()
+        .find(|path| path.file_stem().is_some_and(|stem| stem == spec))
+        .ok_or_else(|| AppError::Other(format!(
+            "No snapshot '{}' for {}/{}", spec, config.owner, config.repo
+        )))
+}
+
+/// Saves a crawl to the default snapshot store.
+pub fn save_crawl(config: &Config, crawl: &Crawl) -> Result<PathBuf, AppError> {
+    let path = Snapshot::new(config, crawl, SystemTime::now()).save(&default_snapshot_root())?;
+    if !config.quiet {
+        eprintln!("Saved snapshot {}", path.display());
+    }
+    Ok(path)
+}
+
+/// Compares two snapshots. Added and removed dependents are sorted by stars,
+/// star changes by the size of the change.
+pub fn diff_snapshots(old: &Snapshot, new: &Snapshot) -> SnapshotDiff {
+    let old_deps: HashMap<&str, &Dependent> = old.dependents.iter().map(|d| (d.repo.as_str(), d)).collect();
+    let new_deps: HashMap<&str, &Dependent> = new.dependents.iter().map(|d| (d.repo.as_str(), d)).collect();
+
+    let mut added: Vec<Dependent> = new.dependents.iter()
+        .filter(|dep| !old_deps.contains_key(dep.repo.as_str()))
+        .cloned()
+        .collect();
+    let mut removed: Vec<Dependent> = old.dependents.iter()
+        .filter(|dep| !new_deps.contains_key(dep.repo.as_str()))
+        .cloned()
+        .collect();
+
+    let mut star_changes: Vec<StarChange> = new.dependents.iter()
+        .filter_map(|dep| {
+            let before = old_deps.get(dep.repo.as_str())?;
+            let delta = convert_stars_to_number(&dep.stars) - convert_stars_to_number(&before.stars);
+            (delta != 0.0).then(|| StarChange {
+                repo: dep.repo.clone(),
+                from: before.stars.clone(),
+                to: dep.stars.clone(),
+                delta,
+            })
+        })
+        .collect();
+
+    let by_stars = |a: &Dependent, b: &Dependent| {
+        convert_stars_to_number(&b.stars).partial_cmp(&convert_stars_to_number(&a.stars)).unwrap()
+            .then_with(|| a.repo.cmp(&b.repo))
+    };
+    added.sort_by(by_stars);
+    removed.sort_by(by_stars);
+    star_changes.sort_by(|a, b| {
+        b.delta.abs().partial_cmp(&a.delta.abs()).unwrap()
+            .then_with(|| a.repo.cmp(&b.repo))
+    });
+
+    SnapshotDiff {
+        from: old.taken_at,
+        to: new.taken_at,
+        added,
+        removed,
+        star_changes,
+    }
+}
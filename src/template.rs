@@ -0,0 +1,90 @@
+use std::str::FromStr;
+
+use crate::dependent::Dependent;
+use crate::error::AppError;
+use crate::output::Column;
+
+#[derive(Debug, Clone, PartialEq)]
+enum TemplatePart {
+    Literal(String),
+    Field(Column),
+}
+
+/// A user supplied per-dependent line format such as `"{rank}. {repo} ({stars})"`.
+///
+/// Placeholders are column names; `{{` and `}}` produce literal braces and
+/// `\n`, `\t` and `\\` are unescaped so templates can be passed from a shell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+impl Template {
+    pub fn render(&self, base_url: &str, rank: usize, dep: &Dependent) -> String {
+        let mut line = String::new();
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(text) => line.push_str(text),
+                TemplatePart::Field(column) => line.push_str(&column.value(base_url, rank, dep)),
+            }
+        }
+        line
+    }
+}
+
+impl FromStr for Template {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                },
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                },
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(AppError::Other(format!(
+                                "Unclosed placeholder '{{{}' in template", name
+                            ))),
+                        }
+                    }
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(TemplatePart::Field(name.trim().parse::<Column>()?));
+                },
+                '}' => {
+                    return Err(AppError::Other(
+                        "Unmatched '}' in template (use '}}' for a literal brace)".to_string()
+                    ));
+                },
+                '\\' => match chars.peek() {
+                    Some('n') => { chars.next(); literal.push('\n'); },
+                    Some('t') => { chars.next(); literal.push('\t'); },
+                    Some('\\') => { chars.next(); literal.push('\\'); },
+                    _ => literal.push('\\'),
+                },
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+
+        Ok(Template { parts })
+    }
+}
@@ -0,0 +1,116 @@
+use crate::batch::{build_leaderboard, read_targets, run_batch};
+use crate::client::{create_client, ClientOptions};
+use crate::config::Config;
+use crate::error::exit_code;
+use crate::github::{list_org_repos, Crawler};
+use crate::progress::ProgressMode;
+use super::dep;
+
+#[test]
+fn test_read_targets() {
+    let path = std::env::temp_dir().join("ghtopdep_batch_targets.txt");
+    std::fs::write(&path, "# our libraries\nowner/lib-a\n\n  owner/lib-b  \nowner/lib-a\n").unwrap();
+
+    let args = vec![path.display().to_string(), "owner/lib-c".to_string(), "owner/lib-b".to_string()];
+    let targets = read_targets(&args).unwrap();
+    assert_eq!(targets, vec!["owner/lib-a", "owner/lib-b", "owner/lib-c"]);
+
+    std::fs::write(&path, "# nothing here\n").unwrap();
+    assert!(read_targets(&[path.display().to_string()]).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_build_leaderboard() {
+    let a = vec![dep("x/shared", "10"), dep("x/popular", "5k"), dep("x/only-a", "1k")];
+    let b = vec![dep("x/shared", "12"), dep("x/popular", "5k")];
+    let c = vec![dep("x/shared", "11")];
+
+    let leaderboard = build_leaderboard(
+        [("o/a", a.as_slice()), ("o/b", b.as_slice()), ("o/c", c.as_slice())],
+        2,
+    );
+
+    let repos: Vec<&str> = leaderboard.iter().map(|e| e.repo.as_str()).collect();
+    assert_eq!(repos, vec!["x/shared", "x/popular"]);
+    assert_eq!(leaderboard[0].stars, "12");
+    assert_eq!(leaderboard[0].targets, vec!["o/a", "o/b", "o/c"]);
+    assert_eq!(leaderboard[1].targets, vec!["o/a", "o/b"]);
+}
+
+#[tokio::test]
+async fn test_run_batch_with_mocks() {
+    use mockito::{mock, server_url};
+
+    let _m1 = mock("GET", "/batch/lib-a/network/dependents?dependent_type=REPOSITORY")
+        .with_status(200)
+        .with_body(include_str!("fixtures/dependents_page.html"))
+        .create();
+    let _m2 = mock("GET", "/batch/lib-b/network/dependents?dependent_type=REPOSITORY")
+        .with_status(200)
+        .with_body(include_str!("fixtures/dependents_page.html"))
+        .create();
+    let _missing = mock("GET", "/batch/missing/network/dependents?dependent_type=REPOSITORY")
+        .with_status(404)
+        .create();
+
+    let config = Config {
+        top_n: 2,
+        max_pages: 1,
+        use_cache: false,
+        base_url: server_url(),
+        progress: ProgressMode::Never,
+        quiet: true,
+        ..Config::default()
+    };
+    let targets = vec![
+        "batch/lib-a".to_string(),
+        "batch/lib-b".to_string(),
+        "not-a-repo".to_string(),
+        "batch/missing".to_string(),
+    ];
+
+    let crawler = Crawler::new(&ClientOptions::default()).unwrap();
+    let batch = run_batch(&crawler, &config, &targets, 2, Some("user1")).await.unwrap();
+    assert_eq!(batch.reports.len(), 2);
+    assert_eq!(batch.reports[0].target, "batch/lib-a");
+    assert_eq!(batch.reports[0].top_dependents.len(), 2);
+    assert_eq!(batch.reports[0].crawl.dependents.len(), 3);
+    assert_eq!(batch.errors.len(), 2);
+    assert_eq!(batch.errors[0].0, "not-a-repo");
+    assert_eq!(batch.errors[1].0, "batch/missing");
+    // user1/repo1 belongs to the excluded owner
+    assert_eq!(batch.leaderboard.len(), 2);
+    assert!(batch.leaderboard.iter().all(|e| e.targets == vec!["batch/lib-a", "batch/lib-b"]));
+    assert_eq!(batch.summary[1].collected, 3);
+    assert_eq!(batch.summary[1].external, 2);
+    assert_eq!(batch.summary[1].shared, 2);
+    // A missing repository outranks an invalid spec
+    assert_eq!(batch.most_severe_error().unwrap().exit_code(), exit_code::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_list_org_repos() {
+    use mockito::{mock, server_url};
+
+    let _m = mock("GET", "/api/v3/orgs/acme/repos?type=public&per_page=100&page=1")
+        .with_status(200)
+        .with_body(r#"[
+            {"full_name": "acme/core", "fork": false, "archived": false},
+            {"full_name": "acme/old", "fork": false, "archived": true},
+            {"full_name": "acme/patched-dep", "fork": true, "archived": false},
+            {"full_name": "acme/cli"}
+        ]"#)
+        .create();
+
+    let config = Config {
+        base_url: server_url(),
+        ..Config::default()
+    };
+    assert_eq!(config.api_url(), format!("{}/api/v3", server_url()));
+    assert_eq!(Config::default().api_url(), "https://api.github.com");
+
+    let client = create_client().unwrap();
+    let repos = list_org_repos(&client, &config.api_url(), "acme", None).await.unwrap();
+    assert_eq!(repos, vec!["acme/core", "acme/cli"]);
+}
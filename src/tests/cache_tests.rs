@@ -40,6 +40,32 @@ fn test_cache_write_read() {
     fs::remove_file(&cache_path).unwrap();
 }
 
+#[tokio::test]
+async fn test_corrupt_cache_entry() {
+    use std::error::Error;
+    use crate::error::AppError;
+    use crate::fetcher::{FetchResponse, MemoryFetcher};
+    use crate::github::cached_fetch;
+    
+    let url = "https://test.example.com/corrupt";
+    let cache_path = get_cache_path(url);
+    fs::write(&cache_path, "not gzip").unwrap();
+    
+    let err = read_from_cache(&cache_path).unwrap_err();
+    assert!(matches!(&err, AppError::CacheCorrupt { path, .. } if *path == cache_path));
+    assert!(err.source().is_some_and(|source| source.source().is_some()));
+    
+    // Crawls recover by fetching the page again and replacing the entry
+    let fetcher = MemoryFetcher::new();
+    fetcher.respond(url, FetchResponse::new(200, "fresh"));
+    let html = cached_fetch(&fetcher, url, Some(std::time::Duration::from_secs(3600))).await.unwrap();
+    assert_eq!(html, "fresh");
+    assert_eq!(fetcher.requests().len(), 1);
+    assert_eq!(read_from_cache(&cache_path).unwrap(), "fresh");
+    
+    fs::remove_file(&cache_path).unwrap();
+}
+
 #[test]
 fn test_cache_validity() {
     let test_url = "https://test.example.com/validity";
@@ -61,4 +87,17 @@ fn test_cache_validity() {
     
     // Clean up
     fs::remove_file(&cache_path).unwrap();
-}
\ No newline at end of file
+}
+#[test]
+fn test_cache_stats() {
+    use crate::cache::cache_stats;
+    
+    let cache_path = get_cache_path("https://test.example.com/stats");
+    write_to_cache(&cache_path, "stats").unwrap();
+    
+    let (entries, total_size) = cache_stats().unwrap();
+    assert!(entries >= 1);
+    assert!(total_size > 0);
+    
+    fs::remove_file(&cache_path).unwrap();
+}
@@ -0,0 +1,51 @@
+use std::ffi::OsString;
+use clap::Parser;
+use crate::cli::{normalize_args, CacheAction, Cli, Commands};
+
+fn normalized(args: &[&str]) -> Vec<String> {
+    normalize_args(args.iter().copied())
+        .into_iter()
+        .map(|arg: OsString| arg.into_string().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_bare_invocation_is_top() {
+    assert_eq!(normalized(&["ghtopdep-rs", "owner/repo", "--rows", "5"]),
+        vec!["ghtopdep-rs", "top", "owner/repo", "--rows", "5"]);
+    assert_eq!(normalized(&["ghtopdep-rs", "--no-cache", "owner/repo"]),
+        vec!["ghtopdep-rs", "top", "--no-cache", "owner/repo"]);
+    assert_eq!(normalized(&["ghtopdep-rs", "--progress", "plain", "owner/repo"]),
+        vec!["ghtopdep-rs", "top", "--progress", "plain", "owner/repo"]);
+}
+
+#[test]
+fn test_subcommands_are_not_rewritten() {
+    assert_eq!(normalized(&["ghtopdep-rs", "count", "owner/repo"]),
+        vec!["ghtopdep-rs", "count", "owner/repo"]);
+    assert_eq!(normalized(&["ghtopdep-rs", "--quiet", "list", "owner/repo"]),
+        vec!["ghtopdep-rs", "--quiet", "list", "owner/repo"]);
+    assert_eq!(normalized(&["ghtopdep-rs", "--help"]), vec!["ghtopdep-rs", "--help"]);
+    assert_eq!(normalized(&["ghtopdep-rs"]), vec!["ghtopdep-rs"]);
+}
+
+#[test]
+fn test_parse_subcommands() {
+    let cli = Cli::try_parse_from(normalize_args(["ghtopdep-rs", "owner/repo", "--quiet"])).unwrap();
+    assert!(matches!(cli.command, Commands::Top(_)));
+    assert!(cli.global.quiet);
+
+    let cli = Cli::try_parse_from(["ghtopdep-rs", "count", "owner/repo", "--packages"]).unwrap();
+    match cli.command {
+        Commands::Count(target) => {
+            assert_eq!(target.repo_url, "owner/repo");
+            assert!(target.packages);
+        },
+        _ => panic!("expected count"),
+    }
+
+    let cli = Cli::try_parse_from(["ghtopdep-rs", "cache", "clear"]).unwrap();
+    assert!(matches!(cli.command, Commands::Cache(ref args) if matches!(args.action, CacheAction::Clear)));
+
+    assert!(Cli::try_parse_from(["ghtopdep-rs", "count", "owner/repo", "--rows", "5"]).is_err());
+}
@@ -56,4 +56,136 @@ async fn test_fetch_with_retry_rate_limit() {
     // This should timeout because it will retry with backoff
     let result = timeout(Duration::from_millis(100), fetch_with_retry(&client, url, 1)).await;
     assert!(result.is_err()); // Timeout error
-} 
\ No newline at end of file
+} 
+
+#[tokio::test]
+async fn test_fetch_errors_are_typed() {
+    use crate::client::fetch_with_retry;
+    use crate::error::{exit_code, AppError};
+    use std::time::Duration;
+    
+    let _missing = mock("GET", "/typed/missing").with_status(404).create();
+    let _broken = mock("GET", "/typed/broken").with_status(500).create();
+    let _throttled = mock("GET", "/typed/throttled")
+        .with_status(429)
+        .with_header("retry-after", "120")
+        .create();
+    let client = create_client().unwrap();
+    
+    let err = fetch_with_retry(&client, &format!("{}/typed/missing", server_url()), 0).await.unwrap_err();
+    assert!(matches!(&err, AppError::NotFound { url } if url.ends_with("/typed/missing")));
+    assert_eq!(err.exit_code(), exit_code::NOT_FOUND);
+    
+    let err = fetch_with_retry(&client, &format!("{}/typed/broken", server_url()), 0).await.unwrap_err();
+    assert!(matches!(err, AppError::HttpStatus { code: 500, .. }));
+    assert_eq!(err.exit_code(), exit_code::NETWORK);
+    
+    let err = fetch_with_retry(&client, &format!("{}/typed/throttled", server_url()), 0).await.unwrap_err();
+    assert!(matches!(err, AppError::RateLimited { retry_after: Some(wait) } if wait == Duration::from_secs(120)));
+    assert_eq!(err.exit_code(), exit_code::RATE_LIMITED);
+    assert_eq!(err.to_string(), "Rate limited by GitHub; retry after 2m");
+}
+
+#[tokio::test]
+async fn test_client_sends_token() {
+    use crate::client::{create_client_with, fetch_with_retry, ClientOptions};
+    
+    let _m = mock("GET", "/authorized")
+        .match_header("authorization", "token secret-token")
+        .with_status(200)
+        .with_body("authorized")
+        .create();
+    
+    let options = ClientOptions {
+        token: Some("secret-token".to_string()),
+        ..ClientOptions::default()
+    };
+    let client = create_client_with(&options).unwrap();
+    let url = &format!("{}/authorized", server_url());
+    
+    let result = fetch_with_retry(&client, url, 0).await;
+    assert_eq!(result.unwrap(), "authorized");
+}
+
+#[test]
+#[cfg(unix)]
+fn test_run_token_command() {
+    use crate::client::run_token_command;
+    
+    assert_eq!(run_token_command("echo '  abc123  '").unwrap(), "abc123");
+    assert!(run_token_command("exit 1").is_err());
+    assert!(run_token_command("true").is_err());
+}
+
+#[tokio::test]
+async fn test_rate_limiter_spaces_requests() {
+    use crate::client::RateLimiter;
+    use std::time::{Duration, Instant};
+
+    let limiter = RateLimiter::new(Duration::from_millis(50), 1);
+    let start = Instant::now();
+    limiter.wait().await;
+    limiter.wait().await;
+    limiter.wait().await;
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn test_client_sends_user_agent() {
+    use crate::client::{create_client_with, fetch_with_retry, ClientOptions, DEFAULT_USER_AGENT};
+
+    let _custom = mock("GET", "/user-agent")
+        .match_header("user-agent", "ghtopdep-ci/1.0")
+        .with_status(200)
+        .with_body("custom")
+        .create();
+    let _default = mock("GET", "/default-user-agent")
+        .match_header("user-agent", DEFAULT_USER_AGENT)
+        .with_status(200)
+        .with_body("default")
+        .create();
+
+    let options = ClientOptions {
+        user_agent: Some("ghtopdep-ci/1.0".to_string()),
+        ..ClientOptions::default()
+    };
+    let client = create_client_with(&options).unwrap();
+    let result = fetch_with_retry(&client, &format!("{}/user-agent", server_url()), 0).await;
+    assert_eq!(result.unwrap(), "custom");
+
+    let client = create_client().unwrap();
+    let result = fetch_with_retry(&client, &format!("{}/default-user-agent", server_url()), 0).await;
+    assert_eq!(result.unwrap(), "default");
+}
+
+#[test]
+fn test_invalid_client_options() {
+    use crate::client::{create_client_with, ClientOptions};
+
+    let dir = std::env::temp_dir().join("ghtopdep_client_options_test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let empty_bundle = dir.join("empty.pem");
+    std::fs::write(&empty_bundle, "").unwrap();
+
+    let error = |options: ClientOptions| create_client_with(&options).unwrap_err().to_string();
+    assert!(error(ClientOptions { proxy: Some("http://[::1".to_string()), ..ClientOptions::default() })
+        .contains("Invalid proxy"));
+    assert!(error(ClientOptions { ca_cert: Some(dir.join("missing.pem")), ..ClientOptions::default() })
+        .contains("Could not read"));
+    assert!(error(ClientOptions { ca_cert: Some(empty_bundle.clone()), ..ClientOptions::default() })
+        .contains("No certificates"));
+    assert!(error(ClientOptions { client_cert: Some(empty_bundle), ..ClientOptions::default() })
+        .contains("Invalid client certificate"));
+    assert!(error(ClientOptions { client_key: Some(dir.join("key.pem")), ..ClientOptions::default() })
+        .contains("needs a client certificate"));
+    assert!(error(ClientOptions { user_agent: Some("bad\nagent".to_string()), ..ClientOptions::default() })
+        .contains("Invalid User-Agent"));
+
+    let proxied = ClientOptions {
+        proxy: Some("http://127.0.0.1:3128".to_string()),
+        connect_timeout: Some(std::time::Duration::from_secs(2)),
+        ..ClientOptions::default()
+    };
+    assert!(create_client_with(&proxied).is_ok());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
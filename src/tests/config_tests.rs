@@ -1,91 +1,263 @@
-use clap::{Arg, ArgAction, Command};
+use clap::error::ErrorKind;
+use clap::Parser;
+use crate::cli::{normalize_args, Cli, Commands};
+use crate::commands::usage_error;
 use crate::config::Config;
+use crate::dependent::TypeSelection;
+use crate::output::{Column, OutputFormat};
+use crate::progress::ProgressMode;
+use crate::settings::Settings;
+
+fn parse_config(args: &[&str]) -> Result<Config, clap::Error> {
+    parse_config_with(args, &Settings::default())
+}
+
+fn parse_config_with(args: &[&str], settings: &Settings) -> Result<Config, clap::Error> {
+    let cli = Cli::try_parse_from(normalize_args(args.iter().copied()))?;
+    let config = match &cli.command {
+        Commands::Top(top) => Config::from_args(&cli.global, settings, top),
+        Commands::List(query) => Config::from_query(&cli.global, settings, query, usize::MAX),
+        _ => panic!("expected a top or list command"),
+    };
+    config.map_err(usage_error)
+}
+
+fn assert_invalid(args: &[&str], kind: ErrorKind) {
+    match parse_config(args) {
+        Ok(_) => panic!("expected {:?} to be rejected", args),
+        Err(err) => {
+            assert_eq!(err.kind(), kind, "unexpected error for {:?}: {}", args, err);
+            assert_eq!(err.exit_code(), 2);
+        }
+    }
+}
 
 #[test]
 fn test_parse_repo_url_github_format() {
-    let matches = Command::new("test")
-        .arg(Arg::new("repo_url").required(true).index(1).default_value("https://github.com/owner/repo"))
-        .arg(Arg::new("top_n").long("rows").default_value("10"))
-        .arg(Arg::new("max_pages").long("max_pages").default_value("100"))
-        .arg(Arg::new("min_stars").long("minstar").default_value("0"))
-        .arg(Arg::new("packages").long("packages").action(ArgAction::SetTrue))
-        .arg(Arg::new("description").long("description").action(ArgAction::SetTrue))
-        .arg(Arg::new("no-cache").long("no-cache").action(ArgAction::SetTrue))
-        .arg(Arg::new("format").long("format").default_value("table"))
-        .arg(Arg::new("table").long("table").action(ArgAction::SetTrue))
-        .get_matches_from(vec!["test", "https://github.com/owner/repo"]);
-    
-    let config = Config::from_matches(&matches).unwrap();
+    let config = parse_config(&["test", "https://github.com/owner/repo"]).unwrap();
     assert_eq!(config.owner, "owner");
     assert_eq!(config.repo, "repo");
 }
 
 #[test]
 fn test_parse_repo_url_short_format() {
-    let matches = Command::new("test")
-        .arg(Arg::new("repo_url").required(true).index(1).default_value("owner/repo"))
-        .arg(Arg::new("top_n").long("rows").default_value("10"))
-        .arg(Arg::new("max_pages").long("max_pages").default_value("100"))
-        .arg(Arg::new("min_stars").long("minstar").default_value("0"))
-        .arg(Arg::new("packages").long("packages").action(ArgAction::SetTrue))
-        .arg(Arg::new("description").long("description").action(ArgAction::SetTrue))
-        .arg(Arg::new("no-cache").long("no-cache").action(ArgAction::SetTrue))
-        .arg(Arg::new("format").long("format").default_value("table"))
-        .arg(Arg::new("table").long("table").action(ArgAction::SetTrue))
-        .get_matches_from(vec!["test", "owner/repo"]);
-    
-    let config = Config::from_matches(&matches).unwrap();
+    let config = parse_config(&["test", "owner/repo"]).unwrap();
     assert_eq!(config.owner, "owner");
     assert_eq!(config.repo, "repo");
 }
 
 #[test]
 fn test_dependent_type() {
-    let matches = Command::new("test")
-        .arg(Arg::new("repo_url").required(true).index(1).default_value("owner/repo"))
-        .arg(Arg::new("top_n").long("rows").default_value("10"))
-        .arg(Arg::new("max_pages").long("max_pages").default_value("100"))
-        .arg(Arg::new("min_stars").long("minstar").default_value("0"))
-        .arg(Arg::new("packages").long("packages").action(ArgAction::SetTrue))
-        .arg(Arg::new("description").long("description").action(ArgAction::SetTrue))
-        .arg(Arg::new("no-cache").long("no-cache").action(ArgAction::SetTrue))
-        .arg(Arg::new("format").long("format").default_value("table"))
-        .arg(Arg::new("table").long("table").action(ArgAction::SetTrue))
-        .get_matches_from(vec!["test", "owner/repo", "--packages"]);
-    
-    let config = Config::from_matches(&matches).unwrap();
+    let config = parse_config(&["test", "owner/repo", "--packages"]).unwrap();
     assert_eq!(config.dependent_type(), "PACKAGE");
     
-    let matches = Command::new("test")
-        .arg(Arg::new("repo_url").required(true).index(1).default_value("owner/repo"))
-        .arg(Arg::new("top_n").long("rows").default_value("10"))
-        .arg(Arg::new("max_pages").long("max_pages").default_value("100"))
-        .arg(Arg::new("min_stars").long("minstar").default_value("0"))
-        .arg(Arg::new("packages").long("packages").action(ArgAction::SetTrue))
-        .arg(Arg::new("description").long("description").action(ArgAction::SetTrue))
-        .arg(Arg::new("no-cache").long("no-cache").action(ArgAction::SetTrue))
-        .arg(Arg::new("format").long("format").default_value("table"))
-        .arg(Arg::new("table").long("table").action(ArgAction::SetTrue))
-        .get_matches_from(vec!["test", "owner/repo"]);
-    
-    let config = Config::from_matches(&matches).unwrap();
+    let config = parse_config(&["test", "owner/repo"]).unwrap();
     assert_eq!(config.dependent_type(), "REPOSITORY");
 }
 
+#[test]
+fn test_dependent_type_selection() {
+    let config = parse_config(&["test", "owner/repo", "--type", "packages"]).unwrap();
+    assert_eq!(config.type_selection, TypeSelection::Packages);
+    assert_eq!(config.dependent_type(), "PACKAGE");
+
+    let config = parse_config(&["test", "owner/repo", "--type", "both", "--merge"]).unwrap();
+    assert!(config.merge_types);
+    assert_eq!(config.type_label(), "repository and package");
+    let types: Vec<&str> = config.type_configs().iter().map(|c| c.dependent_type()).collect();
+    assert_eq!(types, vec!["REPOSITORY", "PACKAGE"]);
+
+    assert_eq!(parse_config(&["test", "owner/repo"]).unwrap().type_configs().len(), 1);
+    assert_invalid(&["test", "owner/repo", "--type", "both", "--packages"], ErrorKind::ArgumentConflict);
+    assert_invalid(&["test", "owner/repo", "--merge"], ErrorKind::ArgumentConflict);
+    assert_invalid(&["test", "owner/repo", "--type", "all"], ErrorKind::InvalidValue);
+
+    let config = parse_config(&["test", "owner/repo", "--depth", "2", "--budget", "5"]).unwrap();
+    assert_eq!((config.depth, config.crawl_budget, config.expand_min_stars), (2, 5, 100.0));
+    assert_invalid(&["test", "owner/repo", "--depth", "0"], ErrorKind::ValueValidation);
+
+    let cli = Cli::try_parse_from(["test", "watch", "owner/repo", "--type", "both"]).unwrap();
+    match &cli.command {
+        Commands::Watch(args) => assert!(Config::from_watch(&cli.global, &Settings::default(), args).is_err()),
+        _ => panic!("expected watch"),
+    }
+}
+
+#[test]
+fn test_filters() {
+    let config = parse_config(&[
+        "test", "owner/repo", "--filter", "stars >= 10", "--exclude-owner", "a,b", "--include-regex", "^x",
+    ]).unwrap();
+    assert_eq!(config.filters.len(), 3);
+    assert!(parse_config(&["test", "owner/repo"]).unwrap().filters.is_empty());
+
+    assert_invalid(&["test", "owner/repo", "--filter", "size > 3"], ErrorKind::ValueValidation);
+    assert_invalid(&["test", "owner/repo", "--include-regex", "("], ErrorKind::ValueValidation);
+    assert_invalid(&["test", "owner/repo", "--exclude-file", "/nonexistent/ghtopdep-exclude"], ErrorKind::ArgumentConflict);
+}
+
 #[test]
 fn test_invalid_repo_url() {
-    let matches = Command::new("test")
-        .arg(Arg::new("repo_url").required(true).index(1).default_value("invalid-format"))
-        .arg(Arg::new("top_n").long("rows").default_value("10"))
-        .arg(Arg::new("max_pages").long("max_pages").default_value("100"))
-        .arg(Arg::new("min_stars").long("minstar").default_value("0"))
-        .arg(Arg::new("packages").long("packages").action(ArgAction::SetTrue))
-        .arg(Arg::new("description").long("description").action(ArgAction::SetTrue))
-        .arg(Arg::new("no-cache").long("no-cache").action(ArgAction::SetTrue))
-        .arg(Arg::new("format").long("format").default_value("table"))
-        .arg(Arg::new("table").long("table").action(ArgAction::SetTrue))
-        .get_matches_from(vec!["test", "invalid-format"]);
-    
-    let result = Config::from_matches(&matches);
+    let result = parse_config(&["test", "invalid-format"]);
     assert!(result.is_err());
-} 
\ No newline at end of file
+    let err = crate::config::Config::default().with_target("invalid-format").unwrap_err();
+    assert!(matches!(err, crate::error::AppError::InvalidRepoSpec(ref spec) if spec == "invalid-format"));
+    assert_eq!(err.exit_code(), crate::error::exit_code::USAGE);
+    
+    assert_invalid(&["test", "owner/"], ErrorKind::ArgumentConflict);
+    assert_invalid(&["test", "https://github.com/owner"], ErrorKind::ArgumentConflict);
+}
+
+#[test]
+fn test_progress_mode() {
+    let config = parse_config(&["test", "owner/repo", "--progress", "plain"]).unwrap();
+    assert_eq!(config.progress, ProgressMode::Plain);
+    assert!(!config.quiet);
+
+    let config = parse_config(&["test", "owner/repo", "--quiet"]).unwrap();
+    assert_eq!(config.progress, ProgressMode::Never);
+    assert!(config.quiet);
+
+    assert_invalid(&["test", "owner/repo", "--progress", "sometimes"], ErrorKind::InvalidValue);
+    assert_invalid(&["test", "owner/repo", "--progress", "always", "--quiet"], ErrorKind::ArgumentConflict);
+}
+
+#[test]
+fn test_list_has_no_row_limit() {
+    let config = parse_config(&["test", "list", "owner/repo", "--minstar", "5"]).unwrap();
+    assert_eq!(config.top_n, usize::MAX);
+    assert_eq!(config.min_stars, 5.0);
+
+    let config = parse_config(&["test", "top", "owner/repo", "--rows", "3"]).unwrap();
+    assert_eq!(config.top_n, 3);
+
+    assert_invalid(&["test", "list", "owner/repo", "--rows", "3"], ErrorKind::UnknownArgument);
+}
+
+#[test]
+fn test_invalid_numbers() {
+    assert_invalid(&["test", "owner/repo", "--rows", "abc"], ErrorKind::ValueValidation);
+    assert_invalid(&["test", "owner/repo", "--rows", "0"], ErrorKind::ValueValidation);
+    assert_invalid(&["test", "owner/repo", "--rows", "-3"], ErrorKind::UnknownArgument);
+    assert_invalid(&["test", "owner/repo", "--max_pages", "0"], ErrorKind::ValueValidation);
+    assert_invalid(&["test", "owner/repo", "--max_pages", "1.5"], ErrorKind::ValueValidation);
+    assert_invalid(&["test", "owner/repo", "--minstar", "many"], ErrorKind::ValueValidation);
+    assert_invalid(&["test", "owner/repo", "--minstar=-1"], ErrorKind::ValueValidation);
+    assert_invalid(&["test", "owner/repo", "--minstar", "NaN"], ErrorKind::ValueValidation);
+}
+
+#[test]
+fn test_output_format() {
+    let config = parse_config(&["test", "owner/repo"]).unwrap();
+    assert_eq!(config.output_format, OutputFormat::Table);
+
+    let config = parse_config(&["test", "owner/repo", "--format", "json"]).unwrap();
+    assert_eq!(config.output_format, OutputFormat::Json);
+
+    let config = parse_config(&["test", "owner/repo", "--table"]).unwrap();
+    assert_eq!(config.output_format, OutputFormat::Table);
+
+    assert_invalid(&["test", "owner/repo", "--format", "xml"], ErrorKind::InvalidValue);
+    assert_invalid(&["test", "owner/repo", "--table", "--format", "json"], ErrorKind::ArgumentConflict);
+}
+
+#[test]
+fn test_columns_and_template() {
+    let config = parse_config(&["test", "owner/repo", "--columns", "rank,repo,forks"]).unwrap();
+    assert_eq!(config.columns, Some(vec![Column::Rank, Column::Repo, Column::Forks]));
+    // Column names are the same for --columns, --template and the headers
+    let config = parse_config(&["test", "owner/repo", "--columns", "repo,fork_of,last_commit"]).unwrap();
+    assert_eq!(config.columns, Some(vec![Column::Repo, Column::ForkOf, Column::LastCommit]));
+    assert!(parse_config(&["test", "owner/repo", "--template", "{fork_of} {last_commit}"]).is_ok());
+
+    let config = parse_config(&["test", "owner/repo", "--template", "{repo}"]).unwrap();
+    assert_eq!(config.output_format, OutputFormat::Text);
+    assert!(config.template.is_some());
+
+    let config = parse_config(&["test", "owner/repo", "--template", "{repo}", "--format", "text"]).unwrap();
+    assert_eq!(config.output_format, OutputFormat::Text);
+
+    assert_invalid(&["test", "owner/repo", "--columns", "rank,watchers"], ErrorKind::InvalidValue);
+    assert_invalid(&["test", "owner/repo", "--template", "{watchers}"], ErrorKind::ValueValidation);
+    assert_invalid(&["test", "owner/repo", "--template", "{repo}", "--table"], ErrorKind::ArgumentConflict);
+    assert_invalid(&["test", "owner/repo", "--template", "{repo}", "--columns", "repo"], ErrorKind::ArgumentConflict);
+    assert_invalid(&["test", "owner/repo", "--template", "{repo}", "--format", "json"], ErrorKind::ArgumentConflict);
+    assert_invalid(&["test", "owner/repo", "--columns", "repo", "--format", "json"], ErrorKind::ArgumentConflict);
+}
+
+
+#[test]
+fn test_settings_defaults_and_flag_precedence() {
+    let settings = Settings {
+        format: Some(OutputFormat::Json),
+        rows: Some(25),
+        minstar: Some(50.0),
+        cache_ttl: Some(2),
+        token_command: None,
+        base_url: Some("https://github.example.com/".to_string()),
+        selectors: None,
+        proxy: None,
+        ca_cert: None,
+        client_cert: None,
+        client_key: None,
+        connect_timeout: None,
+        timeout: None,
+        user_agent: None,
+    };
+
+    let config = parse_config_with(&["test", "owner/repo"], &settings).unwrap();
+    assert_eq!(config.output_format, OutputFormat::Json);
+    assert_eq!(config.top_n, 25);
+    assert_eq!(config.min_stars, 50.0);
+    assert_eq!(config.cache_ttl(), Some(std::time::Duration::from_secs(2 * 3600)));
+    assert_eq!(config.dependents_url(), "https://github.example.com/owner/repo/network/dependents");
+
+    let config = parse_config_with(
+        &["test", "owner/repo", "--rows", "3", "--minstar", "1", "--table", "--no-cache"],
+        &settings,
+    ).unwrap();
+    assert_eq!(config.output_format, OutputFormat::Table);
+    assert_eq!(config.top_n, 3);
+    assert_eq!(config.min_stars, 1.0);
+    assert_eq!(config.cache_ttl(), None);
+
+    // A template still implies text output when the format only comes from settings
+    let config = parse_config_with(&["test", "owner/repo", "--template", "{repo}"], &settings).unwrap();
+    assert_eq!(config.output_format, OutputFormat::Text);
+}
+
+#[test]
+fn test_network_settings_reach_client_options() {
+    use std::time::Duration;
+
+    let settings = Settings {
+        proxy: Some("http://proxy.corp.example:3128".to_string()),
+        timeout: Some(90),
+        user_agent: Some("ghtopdep-ci".to_string()),
+        ..Settings::default()
+    };
+    let options = parse_config_with(&["test", "owner/repo"], &settings).unwrap().client_options().unwrap();
+    assert_eq!(options.proxy.as_deref(), Some("http://proxy.corp.example:3128"));
+    assert_eq!(options.timeout, Some(Duration::from_secs(90)));
+    assert_eq!(options.connect_timeout, None);
+    assert_eq!(options.user_agent.as_deref(), Some("ghtopdep-ci"));
+    assert!(options.token.is_none());
+}
+
+#[test]
+fn test_batch_config_types() {
+    let batch_config = |args: &[&str]| {
+        let cli = Cli::try_parse_from(normalize_args(args.iter().copied())).unwrap();
+        match &cli.command {
+            Commands::Batch(batch) => Config::from_batch(&cli.global, &Settings::default(), batch).unwrap(),
+            _ => panic!("expected a batch command"),
+        }
+    };
+
+    let config = batch_config(&["test", "batch", "a/b", "c/d", "--packages"]);
+    assert!(config.is_package);
+    assert_eq!(config.type_selection, TypeSelection::Packages);
+    let config = batch_config(&["test", "batch", "a/b", "c/d"]);
+    assert!(!config.is_package);
+    assert_eq!(config.type_selection, TypeSelection::Repositories);
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::client::fetch_with_retry;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::fetcher::{FetchResponse, Fetcher, MemoryFetcher};
+use crate::github::Crawler;
+
+const FIRST_PAGE: &str = "https://github.com/fake-owner/lib/network/dependents?dependent_type=REPOSITORY";
+const SECOND_PAGE: &str = "https://github.com/fake-owner/lib/network/dependents?dependent_type=REPOSITORY&dependents_after=MQ";
+
+fn dependents_page(rows: &[(&str, &str)], next: Option<&str>) -> String {
+    let rows: String = rows.iter()
+        .map(|(repo, stars)| format!(
+            r#"<div class="flex-items-center"><a class="text-bold" href="/{0}">{0}</a><div><span>{1}</span></div></div>"#,
+            repo, stars
+        ))
+        .collect();
+    let next = next
+        .map(|url| format!(r#"<div class="paginate-container"><a href="{}">Next</a></div>"#, url.replace('&', "&amp;")))
+        .unwrap_or_default();
+    format!(
+        r#"<div class="table-list-header-toggle"><a class="btn-link selected">3 Repositories</a><a class="btn-link">0 Packages</a></div>
+        <div class="Box">{}</div>{}"#,
+        rows, next
+    )
+}
+
+#[tokio::test]
+async fn test_memory_fetcher() {
+    let fetcher = MemoryFetcher::new();
+    fetcher
+        .respond("https://example.com/a", FetchResponse::new(429, ""))
+        .respond("https://example.com/a", FetchResponse::new(200, "a"))
+        .fail("https://example.com/b", "connection reset");
+
+    assert_eq!(fetcher.get("https://example.com/a").await.unwrap().status, 429);
+    assert_eq!(fetcher.get("https://example.com/a").await.unwrap().body, "a");
+    // The last response is repeated
+    assert_eq!(fetcher.get("https://example.com/a").await.unwrap().body, "a");
+    assert!(fetcher.get("https://example.com/b").await.is_err());
+    assert_eq!(fetcher.get("https://example.com/c").await.unwrap().status, 404);
+    assert_eq!(fetcher.requests().len(), 5);
+}
+
+#[tokio::test]
+async fn test_crawl_follows_pages() {
+    let fetcher = Arc::new(MemoryFetcher::new());
+    fetcher.respond(FIRST_PAGE, FetchResponse::new(200, dependents_page(&[("a/one", "10"), ("b/two", "1.5k")], Some(SECOND_PAGE))));
+    fetcher.respond(SECOND_PAGE, FetchResponse::new(200, dependents_page(&[("c/three", "7")], None)));
+    let crawler = Crawler::with_fetcher(fetcher.clone());
+    let config = Config {
+        owner: "fake-owner".to_string(),
+        repo: "lib".to_string(),
+        use_cache: false,
+        quiet: true,
+        ..Config::default()
+    };
+
+    let crawl = crawler.crawl(&config).await.unwrap();
+    assert_eq!(crawl.max_deps, 3);
+    let repos: Vec<&str> = crawl.dependents.iter().map(|dep| dep.repo.as_str()).collect();
+    assert_eq!(repos, vec!["b/two", "a/one", "c/three"]);
+    // The first page is read once, for its header counts and its dependents
+    assert_eq!(fetcher.requests(), vec![FIRST_PAGE, SECOND_PAGE]);
+}
+
+#[tokio::test]
+async fn test_fetch_retries_rate_limited_requests() {
+    let fetcher = MemoryFetcher::new();
+    fetcher
+        .respond("https://example.com/limited", FetchResponse::new(429, ""))
+        .respond("https://example.com/limited", FetchResponse::new(200, "finally"));
+
+    assert_eq!(fetch_with_retry(&fetcher, "https://example.com/limited", 1).await.unwrap(), "finally");
+
+    let limited = MemoryFetcher::new();
+    limited.respond("https://example.com/limited", FetchResponse {
+        retry_after: Some(Duration::from_secs(60)),
+        ..FetchResponse::new(429, "")
+    });
+    match fetch_with_retry(&limited, "https://example.com/limited", 0).await {
+        Err(AppError::RateLimited { retry_after }) => assert_eq!(retry_after, Some(Duration::from_secs(60))),
+        other => panic!("expected a rate limit error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_fetch_waits_for_retry_after() {
+    let fetcher = MemoryFetcher::new();
+    fetcher
+        .respond("https://example.com/throttled", FetchResponse {
+            retry_after: Some(Duration::from_secs(2)),
+            ..FetchResponse::new(429, "")
+        })
+        .respond("https://example.com/throttled", FetchResponse::new(200, "after the wait"));
+
+    // The first backoff is one second; GitHub asked for two
+    let started = Instant::now();
+    assert_eq!(fetch_with_retry(&fetcher, "https://example.com/throttled", 1).await.unwrap(), "after the wait");
+    assert!(started.elapsed() >= Duration::from_secs(2), "retried after {:?}", started.elapsed());
+    assert_eq!(fetcher.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn test_fetch_retries_network_errors() {
+    let fetcher = MemoryFetcher::new();
+    fetcher
+        .fail("https://example.com/flaky", "connection reset")
+        .respond("https://example.com/flaky", FetchResponse::new(200, "recovered"));
+
+    assert_eq!(fetch_with_retry(&fetcher, "https://example.com/flaky", 1).await.unwrap(), "recovered");
+    assert_eq!(fetcher.requests().len(), 2);
+
+    let down = MemoryFetcher::new();
+    down.fail("https://example.com/down", "connection refused");
+    let err = fetch_with_retry(&down, "https://example.com/down", 0).await.unwrap_err();
+    assert_eq!(err.to_string(), "Error: connection refused");
+}
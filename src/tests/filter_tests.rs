@@ -0,0 +1,126 @@
+use crate::dependent::Dependent;
+use crate::filter::Filter;
+
+fn dep(repo: &str, stars: &str, forks: Option<&str>, description: Option<&str>) -> Dependent {
+    Dependent {
+        forks: forks.map(str::to_string),
+        description: description.map(str::to_string),
+        ..super::dep(repo, stars)
+    }
+}
+
+fn matching(filter: &str, dependents: &[Dependent]) -> Vec<String> {
+    let filter = filter.parse::<Filter>().unwrap();
+    dependents.iter().filter(|d| filter.matches(d)).map(|d| d.repo.clone()).collect()
+}
+
+fn sample() -> Vec<Dependent> {
+    vec![
+        dep("our-org/app", "2.1k", Some("40"), Some("Our app")),
+        dep("alice/tool", "150", Some("3"), None),
+        dep("bob/tool-fork", "120", None, None),
+        dep("carol/mirror-of-tool", "9", None, Some("Mirror")),
+    ]
+}
+
+#[test]
+fn test_filter_expressions() {
+    let deps = sample();
+
+    assert_eq!(
+        matching("stars >= 100 && !owner in [our-org] && name !~ /fork|mirror/", &deps),
+        vec!["alice/tool"]
+    );
+    assert_eq!(matching("stars > 1k", &deps), vec!["our-org/app"]);
+    assert_eq!(matching("forks >= 3 || description ~ /mirror/i", &deps),
+        vec!["our-org/app", "alice/tool", "carol/mirror-of-tool"]);
+    assert_eq!(matching("!(stars < 100) && repo != OUR-ORG/app", &deps), vec!["alice/tool", "bob/tool-fork"]);
+    assert_eq!(matching("owner == \"bob\" || name in [app, 'tool']", &deps),
+        vec!["our-org/app", "alice/tool", "bob/tool-fork"]);
+    assert_eq!(matching("type == repository && stars = 9", &deps), vec!["carol/mirror-of-tool"]);
+    assert_eq!(matching("repo ~ /^[a-c].*\\/tool/", &deps), vec!["alice/tool", "bob/tool-fork"]);
+}
+
+#[test]
+fn test_invalid_filters() {
+    for filter in [
+        "",
+        "stars >=",
+        "stars >= many",
+        "size > 3",
+        "owner > bob",
+        "stars ~ /1/",
+        "name ~ /(/",
+        "(stars > 1",
+        "owner in [a, b",
+        "stars > 1 stars",
+        "name ~ /unclosed",
+        "owner == 'unclosed",
+        "stars > 1 & forks > 1",
+    ] {
+        let err = filter.parse::<Filter>();
+        assert!(err.is_err(), "{:?} should be rejected", filter);
+        assert!(err.unwrap_err().to_string().contains("Invalid filter"));
+    }
+}
+
+#[test]
+fn test_filter_shortcuts() {
+    let deps = sample();
+
+    let filter = Filter::exclude_owners(&["Our-Org".to_string(), "bob".to_string()]);
+    let kept: Vec<&str> = deps.iter().filter(|d| filter.matches(d)).map(|d| d.repo.as_str()).collect();
+    assert_eq!(kept, vec!["alice/tool", "carol/mirror-of-tool"]);
+
+    let filter = Filter::include_regex(regex::Regex::new("tool$").unwrap());
+    let kept: Vec<&str> = deps.iter().filter(|d| filter.matches(d)).map(|d| d.repo.as_str()).collect();
+    assert_eq!(kept, vec!["alice/tool", "carol/mirror-of-tool"]);
+
+    let path = std::env::temp_dir().join("ghtopdep_exclude_file.txt");
+    std::fs::write(&path, "# ours\nour-org\n\nbob/tool-fork\n").unwrap();
+    let filter = Filter::exclude_file(&path).unwrap();
+    let kept: Vec<&str> = deps.iter().filter(|d| filter.matches(d)).map(|d| d.repo.as_str()).collect();
+    assert_eq!(kept, vec!["alice/tool", "carol/mirror-of-tool"]);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(Filter::exclude_file(&std::env::temp_dir().join("ghtopdep_missing_exclude_file")).is_err());
+}
+
+#[test]
+fn test_metadata_filters() {
+    use std::time::{Duration, SystemTime};
+    use crate::dependent::RepoMetadata;
+
+    let year_ago = humantime::format_rfc3339_seconds(SystemTime::now() - Duration::from_secs(400 * 86_400));
+    let last_week = humantime::format_rfc3339_seconds(SystemTime::now() - Duration::from_secs(7 * 86_400));
+    let mut deps = sample();
+    deps[0].metadata = Some(RepoMetadata {
+        topics: vec!["cli".to_string(), "rust".to_string()],
+        language: Some("Rust".to_string()),
+        license: Some("MIT license".to_string()),
+        last_commit: Some(last_week.to_string()),
+        ..RepoMetadata::default()
+    });
+    deps[1].metadata = Some(RepoMetadata {
+        archived: true,
+        language: Some("Go".to_string()),
+        last_commit: Some(year_ago.to_string()),
+        ..RepoMetadata::default()
+    });
+
+    assert_eq!(matching("archived", &deps), vec!["alice/tool"]);
+    assert_eq!(matching("!archived && stars > 100", &deps), vec!["our-org/app", "bob/tool-fork"]);
+    assert_eq!(matching("topics == rust", &deps), vec!["our-org/app"]);
+    assert_eq!(matching("language in [go, python]", &deps), vec!["alice/tool"]);
+    assert_eq!(matching("license ~ /mit/i", &deps), vec!["our-org/app"]);
+    assert_eq!(matching("commit_age <= 365", &deps), vec!["our-org/app"]);
+
+    let filter = Filter::active_within(Duration::from_secs(365 * 86_400));
+    assert_eq!(deps.iter().filter(|d| filter.matches(d)).count(), 1);
+    let filter = Filter::exclude_archived();
+    assert_eq!(deps.iter().filter(|d| filter.matches(d)).count(), 3);
+
+    assert!(filter.needs_metadata());
+    assert!("stars > 1 || language == rust".parse::<Filter>().unwrap().needs_metadata());
+    assert!(!"stars > 1 && owner != bob".parse::<Filter>().unwrap().needs_metadata());
+}
@@ -0,0 +1,116 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::fetcher::{FetchResponse, Fetcher, MemoryFetcher};
+use crate::fixtures::{fixture_key, FixtureRecorder, Fixtures, RecordingFetcher, ReplayFetcher};
+use crate::github::Crawler;
+use crate::output::{display_results, OutputFormat};
+
+#[test]
+fn test_record_fixtures() {
+    let dir = std::env::temp_dir().join("ghtopdep_record_fixtures_test");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let recorder = FixtureRecorder::create(&dir).unwrap();
+    let dependents_url = "https://github.com/a/b/network/dependents?dependent_type=REPOSITORY";
+    recorder.record(dependents_url, &FetchResponse::new(200, "first")).unwrap();
+    recorder.record("https://github.com/a/b", &FetchResponse::new(404, "")).unwrap();
+    let next_page = r#"<a href="https://github.com/a/b?page=2">next</a>"#;
+    recorder.record(dependents_url, &FetchResponse::new(200, next_page)).unwrap();
+
+    let fixtures = Fixtures::load(&dir).unwrap();
+    assert_eq!(fixtures.len(), 2);
+    let response = fixtures.response("/a/b/network/dependents?dependent_type=REPOSITORY")
+        .unwrap().unwrap();
+    assert_eq!(response, FetchResponse::new(200, next_page));
+    assert_eq!(fixtures.response("/a/b").unwrap().unwrap().status, 404);
+    assert!(fixtures.response("/a/c").is_none());
+
+    // A later recording adds to the fixtures, with the wait of a 429
+    let limited = FetchResponse {
+        retry_after: Some(std::time::Duration::from_secs(30)),
+        ..FetchResponse::new(429, "")
+    };
+    FixtureRecorder::create(&dir).unwrap().record("https://github.com/c/d", &limited).unwrap();
+    let fixtures = Fixtures::load(&dir).unwrap();
+    assert_eq!(fixtures.len(), 3);
+    assert_eq!(fixtures.response("/c/d").unwrap().unwrap(), limited);
+    assert_eq!(fixtures.response("/a/b").unwrap().unwrap().status, 404);
+
+    assert_eq!(fixture_key("https://github.com/o/r?tab=readme"), "/o/r?tab=readme");
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(Fixtures::load(&dir).is_err());
+}
+
+#[tokio::test]
+async fn test_replayed_crawl() {
+    let fixtures = Fixtures::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/fixtures/replay")).unwrap();
+    let config = Config {
+        owner: "replay-owner".to_string(),
+        repo: "lib".to_string(),
+        top_n: 3,
+        show_desc: true,
+        use_cache: false,
+        quiet: true,
+        output_format: OutputFormat::Json,
+        ..Config::default()
+    };
+    let crawler = Crawler::with_fetcher(Arc::new(ReplayFetcher::new(fixtures)));
+
+    // Two pages, following the recorded next link
+    let crawl = crawler.crawl(&config).await.unwrap();
+    assert_eq!(crawl.max_deps, 5);
+    assert_eq!(crawl.total_repos_count, 5);
+    let dependents: Vec<(&str, &str)> = crawl.dependents.iter()
+        .map(|dep| (dep.repo.as_str(), dep.stars.as_str()))
+        .collect();
+    assert_eq!(dependents, vec![("alpha/app", "1.2k"), ("gamma/cli", "300"), ("beta/tool", "45"), ("delta/lib", "7")]);
+
+    let top = crawler.top_dependents(&config, &crawl).await;
+    let descriptions: Vec<Option<&str>> = top.iter().map(|dep| dep.description.as_deref()).collect();
+    assert_eq!(descriptions, vec![Some("The alpha application"), Some("Gamma command line"), Some("Beta tooling")]);
+    assert!(display_results(&config, &top, crawl.total_repos_count, crawl.dependents.len(), crawl.max_deps, None, 0.5).is_ok());
+}
+
+#[tokio::test]
+async fn test_recording_fetcher() {
+    let dir = std::env::temp_dir().join("ghtopdep_recording_fetcher_test");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let live = Arc::new(MemoryFetcher::new());
+    live.respond("https://github.com/a/b", FetchResponse::new(200, "repo page"));
+    let recording = RecordingFetcher::new(live, &dir).unwrap();
+    assert_eq!(recording.get("https://github.com/a/b").await.unwrap().body, "repo page");
+    assert_eq!(recording.get("https://github.com/a/c").await.unwrap().status, 404);
+
+    // Replayed under any base URL, by path
+    let replay = ReplayFetcher::new(Fixtures::load(&dir).unwrap());
+    assert_eq!(replay.get("http://localhost:8080/a/b").await.unwrap(), FetchResponse::new(200, "repo page"));
+    assert_eq!(replay.get("https://github.com/a/c").await.unwrap().status, 404);
+    assert_eq!(replay.get("https://github.com/a/d").await.unwrap().status, 404);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_replayed_rate_limit() {
+    use crate::client::fetch_with_retry;
+    use crate::error::AppError;
+    use std::time::Duration;
+
+    let dir = std::env::temp_dir().join("ghtopdep_replayed_rate_limit_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    let live = Arc::new(MemoryFetcher::new());
+    live.respond("https://github.com/a/b", FetchResponse {
+        retry_after: Some(Duration::from_secs(120)),
+        ..FetchResponse::new(429, "")
+    });
+    RecordingFetcher::new(live, &dir).unwrap().get("https://github.com/a/b").await.unwrap();
+
+    let replay = ReplayFetcher::new(Fixtures::load(&dir).unwrap());
+    match fetch_with_retry(&replay, "https://github.com/a/b", 0).await {
+        Err(AppError::RateLimited { retry_after }) => assert_eq!(retry_after, Some(Duration::from_secs(120))),
+        other => panic!("expected a rate limit error, got {:?}", other),
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,6 +1,9 @@
-use crate::github::{parse_page, cached_fetch};
+use crate::github::{parse_page, parse_header_counts, parse_packages, cached_fetch, HeaderCounts, PackageOption};
 use crate::client::create_client;
 use mockito::{mock, server_url};
+use std::time::Duration;
+
+const CACHE_TTL: Duration = Duration::from_secs(3600);
 
 #[test]
 fn test_parse_page_with_dependents() {
@@ -52,6 +55,25 @@ fn test_parse_page_without_next() {
     assert_eq!(next_link, None);
 }
 
+#[test]
+fn test_parse_page_forks() {
+    let html = r#"
+    <div class="Box-row d-flex flex-items-center">
+        <span class="f5"><a class="text-bold" href="/user1/repo1">repo1</a></span>
+        <div class="d-flex flex-auto flex-justify-end">
+            <span class="pl-3"><svg class="octicon octicon-star"></svg> 1,234 </span>
+            <span class="pl-3"><svg class="octicon octicon-repo-forked"></svg> 56 </span>
+        </div>
+    </div>
+    "#;
+    
+    let (dependents, _) = parse_page(html);
+    
+    assert_eq!(dependents.len(), 1);
+    assert_eq!(dependents[0].0, "user1/repo1");
+    assert_eq!(dependents[0].2, Some("56".to_string()));
+}
+
 #[tokio::test]
 async fn test_cached_fetch() {
     // We need a unique URL for this test to avoid interference from other tests
@@ -68,12 +90,12 @@ async fn test_cached_fetch() {
     let url = &format!("{}{}", server_url(), unique_path);
     
     // First fetch should hit the server
-    let result1 = cached_fetch(&client, url, true).await;
+    let result1 = cached_fetch(&client, url, Some(CACHE_TTL)).await;
     assert!(result1.is_ok());
     assert_eq!(result1.unwrap(), "test content");
     
     // Second fetch should use cache - no server request
-    let result2 = cached_fetch(&client, url, true).await;
+    let result2 = cached_fetch(&client, url, Some(CACHE_TTL)).await;
     assert!(result2.is_ok());
     assert_eq!(result2.unwrap(), "test content");
     
@@ -85,7 +107,294 @@ async fn test_cached_fetch() {
         .expect(1)
         .create();
     
-    let result3 = cached_fetch(&client, url, false).await;
+    let result3 = cached_fetch(&client, url, None).await;
     assert!(result3.is_ok());
     assert_eq!(result3.unwrap(), "new content");
-} 
\ No newline at end of file
+} 
+#[test]
+fn test_parse_header_counts() {
+    let html = r#"
+    <div class="table-list-header-toggle states flex-auto pl-0">
+        <a class="btn-link selected" href="?dependent_type=REPOSITORY">
+            <svg class="octicon octicon-code-square"></svg>
+            1,234
+            Repositories
+        </a>
+        <a class="btn-link" href="?dependent_type=PACKAGE">
+            <svg class="octicon octicon-package"></svg>
+            56
+            Packages
+        </a>
+    </div>
+    "#;
+
+    let counts = parse_header_counts(html);
+    assert_eq!(counts, HeaderCounts { repositories: Some(1234), packages: Some(56) });
+    assert_eq!(counts.for_type("PACKAGE"), Some(56));
+    assert_eq!(counts.for_type("REPOSITORY"), Some(1234));
+    assert_eq!(parse_header_counts("<p>nothing</p>"), HeaderCounts::default());
+}
+
+const PACKAGES_PAGE: &str = r#"
+<details class="select-menu">
+    <summary>Package: <span>near-sdk</span></summary>
+    <div class="select-menu-list">
+        <a class="select-menu-item" role="menuitemradio" aria-checked="true"
+           href="/near/near-sdk-rs/network/dependents?dependent_type=REPOSITORY&package_id=UGFja2FnZS0x">
+            <span class="select-menu-item-text">near-sdk</span>
+        </a>
+        <a class="select-menu-item" role="menuitemradio" aria-checked="false"
+           href="/near/near-sdk-rs/network/dependents?package_id=UGFja2FnZS0y">
+            <span class="select-menu-item-text">near-sdk-macros</span>
+        </a>
+        <a class="select-menu-item" href="/near/near-sdk-rs/network/dependents?dependent_type=PACKAGE">Other</a>
+    </div>
+</details>
+"#;
+
+#[test]
+fn test_parse_packages() {
+    let packages = parse_packages(PACKAGES_PAGE);
+    assert_eq!(packages, vec![
+        PackageOption { name: "near-sdk".to_string(), id: "UGFja2FnZS0x".to_string(), selected: true },
+        PackageOption { name: "near-sdk-macros".to_string(), id: "UGFja2FnZS0y".to_string(), selected: false },
+    ]);
+    assert!(parse_packages("<p>single package</p>").is_empty());
+}
+
+#[tokio::test]
+async fn test_package_configs() {
+    use crate::client::ClientOptions;
+    use crate::config::Config;
+    use crate::github::Crawler;
+
+    let _m = mock("GET", "/pkg-owner/monorepo/network/dependents?dependent_type=REPOSITORY")
+        .with_status(200)
+        .with_body(PACKAGES_PAGE)
+        .create();
+
+    let crawler = Crawler::new(&ClientOptions::default()).unwrap();
+    let config = Config {
+        owner: "pkg-owner".to_string(),
+        repo: "monorepo".to_string(),
+        base_url: server_url(),
+        use_cache: false,
+        ..Config::default()
+    };
+
+    let configs = crawler.package_configs(&config).await.unwrap();
+    assert_eq!(configs.len(), 1);
+    assert_eq!(configs[0].package_id, None);
+
+    let selected = Config { package: Some("near-sdk-macros".to_string()), ..config.clone() };
+    let configs = crawler.package_configs(&selected).await.unwrap();
+    assert_eq!(configs[0].package_id.as_deref(), Some("UGFja2FnZS0y"));
+    assert!(configs[0].first_page_url().ends_with("?dependent_type=REPOSITORY&package_id=UGFja2FnZS0y"));
+    assert_eq!(configs[0].section_name(), "near-sdk-macros");
+    assert_eq!(configs[0].snapshot_kind(), "repository-near-sdk-macros");
+
+    let all = Config { all_packages: true, ..config.clone() };
+    let names: Vec<String> = crawler.package_configs(&all).await.unwrap().into_iter()
+        .filter_map(|config| config.package)
+        .collect();
+    assert_eq!(names, vec!["near-sdk", "near-sdk-macros"]);
+
+    let unknown = Config { package: Some("missing".to_string()), ..config };
+    let err = crawler.package_configs(&unknown).await.unwrap_err().to_string();
+    assert!(err.contains("Available packages: near-sdk, near-sdk-macros"), "{}", err);
+}
+
+#[test]
+fn test_parse_fork_parent() {
+    use crate::github::parse_fork_parent;
+
+    let fork = r#"<span class="text-small lh-condensed-ultra no-wrap mt-1">
+        forked from <a class="Link--inline" href="/Upstream/Project">Upstream/Project</a>
+    </span>"#;
+    assert_eq!(parse_fork_parent(fork).as_deref(), Some("upstream/project"));
+
+    let mirror = r#"<span>mirrored from <a href="https://git.example.org/project.git/">git.example.org</a></span>"#;
+    assert_eq!(parse_fork_parent(mirror).as_deref(), Some("https://git.example.org/project.git"));
+
+    let original = r#"<p>A library <a href="/docs">forked from nothing</a></p>"#;
+    assert_eq!(parse_fork_parent(original), None);
+}
+
+#[test]
+fn test_collapse_forks() {
+    use crate::dependent::Dependent;
+    use crate::github::collapse_forks;
+
+    let dep = |repo: &str, stars: &str, fork_of: Option<&str>| Dependent {
+        fork_of: fork_of.map(str::to_string),
+        ..super::dep(repo, stars)
+    };
+    let collapsed = collapse_forks(vec![
+        dep("a/fork", "50", Some("up/project")),
+        dep("up/project", "10", None),
+        dep("b/fork-of-fork", "5", Some("a/fork")),
+        dep("x/other-fork", "30", Some("gone/project")),
+        dep("y/other-fork", "2", Some("gone/project")),
+        dep("z/solo", "1", None),
+    ]);
+
+    let entries: Vec<(&str, usize)> = collapsed.iter()
+        .map(|dep| (dep.repo.as_str(), dep.collapsed_forks))
+        .collect();
+    assert_eq!(entries, vec![("x/other-fork", 1), ("up/project", 2), ("z/solo", 0)]);
+}
+
+#[tokio::test]
+async fn test_crawl_collapse_forks() {
+    use crate::client::ClientOptions;
+    use crate::config::Config;
+    use crate::github::Crawler;
+
+    let page = r#"
+    <div class="flex-items-center"><span><a class="text-bold" href="/fork-up/lib">fork-up/lib</a></span><div><span>9</span></div></div>
+    <div class="flex-items-center"><span><a class="text-bold" href="/fork-a/lib">fork-a/lib</a></span><div><span>3</span></div></div>
+    "#;
+    let _page = mock("GET", "/fork-owner/target/network/dependents?dependent_type=REPOSITORY")
+        .with_status(200)
+        .with_body(page)
+        .create();
+    let _up = mock("GET", "/fork-up/lib")
+        .with_status(200)
+        .with_body("<p>An original project</p>")
+        .create();
+    let _fork = mock("GET", "/fork-a/lib")
+        .with_status(200)
+        .with_body(r#"<span>forked from <a href="/fork-up/lib">fork-up/lib</a></span>"#)
+        .create();
+
+    let crawler = Crawler::new(&ClientOptions::default()).unwrap();
+    let config = Config {
+        owner: "fork-owner".to_string(),
+        repo: "target".to_string(),
+        base_url: server_url(),
+        use_cache: false,
+        collapse_forks: true,
+        quiet: true,
+        max_pages: 1,
+        ..Config::default()
+    };
+
+    let crawl = crawler.crawl(&config).await.unwrap();
+    assert_eq!(crawl.total_repos_count, 2);
+    assert_eq!(crawl.unique_upstreams, Some(1));
+    assert_eq!(crawl.dependents.len(), 1);
+    assert_eq!(crawl.dependents[0].repo, "fork-up/lib");
+    assert_eq!(crawl.dependents[0].collapsed_forks, 1);
+
+    // The raw count is reported alongside the unique upstreams
+    assert_eq!(crawl.matched_count, 2);
+    let json = crate::output::results_json(
+        &crawl.dependents, crawl.total_repos_count, crawl.matched_count, crawl.unique_upstreams, 0.0,
+    );
+    assert_eq!(json["stats"]["repositories_with_stars"], 2);
+    assert_eq!(json["stats"]["unique_upstream_projects"], 1);
+}
+
+#[test]
+fn test_parse_repo_page() {
+    use crate::github::parse_repo_page;
+
+    let html = r##"
+    <div class="flash flash-warn">This repository has been archived by the owner. It is now read-only.</div>
+    <relative-time datetime="2024-03-01T12:00:00Z">Mar 1, 2024</relative-time>
+    <div class="BorderGrid-cell">
+        <h2>About</h2>
+        <p>A handy tool</p>
+        <div class="my-3 d-flex flex-items-center">
+            <svg class="octicon octicon-link"></svg>
+            <span><a href="https://tool.example.com" role="link">tool.example.com</a></span>
+        </div>
+        <a class="topic-tag topic-tag-link" href="/topics/cli">cli</a>
+        <a class="topic-tag topic-tag-link" href="/topics/rust"> rust </a>
+        <a class="Link--muted" href="#MIT-1-ov-file"><svg class="octicon octicon-law"></svg> MIT license</a>
+    </div>
+    <div class="BorderGrid-cell">
+        <h2 class="h4 mb-3">Languages</h2>
+        <ul><li><a href="/owner/tool/search?l=rust"><span class="color-fg-default text-bold mr-1">Rust</span><span>97.5%</span></a></li></ul>
+    </div>
+    "##;
+
+    let page = parse_repo_page(html);
+    assert_eq!(page.description.as_deref(), Some("A handy tool"));
+    assert_eq!(page.fork_of, None);
+    assert_eq!(page.metadata.topics, vec!["cli", "rust"]);
+    assert_eq!(page.metadata.license.as_deref(), Some("MIT license"));
+    assert_eq!(page.metadata.language.as_deref(), Some("Rust"));
+    assert_eq!(page.metadata.homepage.as_deref(), Some("https://tool.example.com"));
+    assert_eq!(page.metadata.last_commit.as_deref(), Some("2024-03-01T12:00:00Z"));
+    assert!(page.metadata.archived);
+
+    let bare = parse_repo_page("<p>Nothing here</p>");
+    assert_eq!(bare.metadata, Default::default());
+}
+
+#[test]
+fn test_classify_page() {
+    use crate::github::{classify_page, PageKind};
+
+    let listing = r#"<div class="flex-items-center"><a class="text-bold" href="/a/b">a/b</a><div><span>3</span></div></div>"#;
+    assert_eq!(classify_page(listing), PageKind::Dependents);
+    assert_eq!(classify_page(include_str!("fixtures/dependents_page.html")), PageKind::Dependents);
+    let no_dependents = r#"
+    <div class="table-list-header-toggle"><a class="btn-link">0 Repositories</a><a class="btn-link">0 Packages</a></div>
+    <div class="blankslate"><p>We haven’t found any dependents for this repository yet.</p></div>
+    "#;
+    assert_eq!(classify_page(no_dependents), PageKind::Empty);
+    assert_eq!(classify_page(r#"<title>Sign in to GitHub · GitHub</title><form action="/session"></form>"#), PageKind::LoginWall);
+    assert_eq!(classify_page("<h1>Whoa there!</h1><p>You have triggered an abuse detection mechanism.</p>"), PageKind::AbuseDetection);
+    assert_eq!(classify_page("<title>Page not found · GitHub</title>"), PageKind::NotFound);
+    assert_eq!(classify_page(r#"<div class="dependents-v2"><a href="/a/b">a/b</a></div>"#), PageKind::Unrecognized);
+}
+
+#[tokio::test]
+async fn test_crawl_fails_on_unrecognized_page() {
+    use crate::client::ClientOptions;
+    use crate::config::Config;
+    use crate::error::AppError;
+    use crate::github::Crawler;
+
+    let redesigned = r#"<div class="dependents-v2"><a href="/a/b">a/b</a></div>"#;
+    let _page = mock("GET", "/layout-owner/target/network/dependents?dependent_type=REPOSITORY")
+        .with_status(200)
+        .with_body(redesigned)
+        .create();
+
+    let debug_dir = std::env::temp_dir().join("ghtopdep_debug_pages_test");
+    let _ = std::fs::remove_dir_all(&debug_dir);
+    let crawler = Crawler::new(&ClientOptions::default()).unwrap();
+    let config = Config {
+        owner: "layout-owner".to_string(),
+        repo: "target".to_string(),
+        base_url: server_url(),
+        use_cache: false,
+        debug_dir: Some(debug_dir.clone()),
+        quiet: true,
+        max_pages: 1,
+        ..Config::default()
+    };
+
+    let err = crawler.crawl(&config).await.err().unwrap();
+    assert!(matches!(&err, AppError::ParseFailure { reason, .. } if reason.contains("changed its layout")));
+    let saved: Vec<_> = std::fs::read_dir(&debug_dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+    assert_eq!(saved.len(), 1);
+    assert!(err.to_string().contains(&saved[0].display().to_string()));
+    assert_eq!(std::fs::read_to_string(&saved[0]).unwrap(), redesigned);
+    std::fs::remove_dir_all(&debug_dir).unwrap();
+}
+
+#[test]
+fn test_classify_page_with_unrecognized_rows() {
+    use crate::github::{classify_page, PageKind};
+
+    // The header still counts dependents, but no row is recognized
+    let html = r#"
+    <div class="table-list-header-toggle"><a class="btn-link">30 Repositories</a><a class="btn-link">0 Packages</a></div>
+    <div class="dependent-v2"><a href="/a/b">a/b</a></div>
+    "#;
+    assert_eq!(classify_page(html), PageKind::Unrecognized);
+}
@@ -0,0 +1,67 @@
+use crate::dependent::{Dependent, DependentType, RepoMetadata};
+use crate::github::HeaderCounts;
+use crate::graph::{render_graph, GraphFormat};
+use crate::tree::{DependencyTree, TreeEdge, TreeNode};
+
+fn sample_tree() -> DependencyTree {
+    let node = |repo: &str, stars: &str, depth: usize, language: Option<&str>| TreeNode {
+        dependent: Dependent {
+            forks: Some("12".to_string()),
+            metadata: language.map(|language| RepoMetadata {
+                language: Some(language.to_string()),
+                ..RepoMetadata::default()
+            }),
+            ..super::dep(repo, stars)
+        },
+        depth,
+        total_dependents: None,
+    };
+    let edge = |dependency: &str, dependent: &str, package: Option<&str>| TreeEdge {
+        dependency: dependency.to_string(),
+        dependent: dependent.to_string(),
+        dependent_type: DependentType::Repository,
+        package: package.map(str::to_string),
+    };
+
+    DependencyTree {
+        root: "owner/lib".to_string(),
+        root_total: 42,
+        nodes: vec![node("a/app", "1.5k", 1, Some("C & \"C++\"")), node("b/tool", "7", 2, None)],
+        edges: vec![edge("owner/lib", "a/app", Some("lib-core")), edge("a/app", "b/tool", None)],
+        crawls: 2,
+        truncated: false,
+        header: HeaderCounts::default(),
+    }
+}
+
+#[test]
+fn test_render_dot() {
+    let dot = render_graph(&sample_tree(), GraphFormat::Dot);
+    assert!(dot.starts_with("digraph dependents {\n"));
+    assert!(dot.contains("  \"owner/lib\" [root=true, dependents=42];\n"));
+    assert!(dot.contains("  \"a/app\" [stars=1500, forks=12, language=\"C & \\\"C++\\\"\", depth=1];\n"), "{}", dot);
+    assert!(dot.contains("  \"a/app\" -> \"owner/lib\" [type=\"repository\", package=\"lib-core\"];\n"));
+    assert!(dot.contains("  \"b/tool\" -> \"a/app\" [type=\"repository\"];\n"));
+    assert!(dot.ends_with("}\n"));
+}
+
+#[test]
+fn test_render_graphml() {
+    let graphml = render_graph(&sample_tree(), GraphFormat::Graphml);
+    assert!(graphml.contains("<key id=\"language\" for=\"node\" attr.name=\"language\" attr.type=\"string\"/>"));
+    assert!(graphml.contains("<data key=\"language\">C &amp; &quot;C++&quot;</data>"), "{}", graphml);
+    assert!(graphml.contains("<edge source=\"a/app\" target=\"owner/lib\"><data key=\"type\">repository</data><data key=\"package\">lib-core</data></edge>"));
+    assert_eq!(graphml.matches("<node ").count(), 3);
+}
+
+#[test]
+fn test_render_jgf() {
+    let jgf: serde_json::Value = serde_json::from_str(&render_graph(&sample_tree(), GraphFormat::Jgf)).unwrap();
+    let graph = &jgf["graph"];
+    assert_eq!(graph["directed"], true);
+    assert_eq!(graph["nodes"].as_object().unwrap().len(), 3);
+    assert_eq!(graph["nodes"]["a/app"]["metadata"]["stars"], 1500.0);
+    assert_eq!(graph["nodes"]["b/tool"]["metadata"]["depth"], 2.0);
+    assert_eq!(graph["edges"][0]["source"], "a/app");
+    assert_eq!(graph["edges"][0]["metadata"]["package"], "lib-core");
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+use mockito::{mock, server_url};
+
+use crate::github::cached_fetch;
+use crate::metrics::{metrics, write_textfile, CacheResult, Metrics, RetryReason};
+
+#[test]
+fn test_render_metrics() {
+    let metrics = Metrics::new();
+    metrics.record_response(200);
+    metrics.record_response(200);
+    metrics.record_response(429);
+    metrics.record_page(1500);
+    metrics.record_page(500);
+    metrics.record_retry(RetryReason::RateLimited);
+    metrics.record_cache(CacheResult::Hit);
+    metrics.record_cache(CacheResult::Corrupt);
+    metrics.record_crawl("owner/repo", "repository", None, Duration::from_millis(2500), 42);
+    metrics.record_crawl("owner/repo", "package", Some("say \"hi\""), Duration::from_secs(1), 7);
+
+    let text = metrics.render();
+    for line in [
+        "# TYPE ghtopdep_pages_fetched_total counter",
+        "ghtopdep_pages_fetched_total 2",
+        "ghtopdep_downloaded_bytes_total 2000",
+        "ghtopdep_http_responses_total{code=\"200\"} 2",
+        "ghtopdep_http_responses_total{code=\"429\"} 1",
+        "ghtopdep_http_retries_total{reason=\"rate_limited\"} 1",
+        "ghtopdep_cache_lookups_total{result=\"hit\"} 1",
+        "ghtopdep_cache_lookups_total{result=\"miss\"} 0",
+        "ghtopdep_cache_lookups_total{result=\"corrupt\"} 1",
+        "ghtopdep_crawls_total{target=\"owner/repo\",type=\"repository\"} 1",
+        "ghtopdep_crawl_duration_seconds{target=\"owner/repo\",type=\"repository\"} 2.5",
+        "ghtopdep_dependents_found{target=\"owner/repo\",type=\"package\",package=\"say \\\"hi\\\"\"} 7",
+    ] {
+        assert!(text.lines().any(|l| l == line), "missing {:?} in\n{}", line, text);
+    }
+    assert!(!text.contains("reason=\"network\""));
+
+    let path = std::env::temp_dir().join("ghtopdep_metrics_test.prom");
+    write_textfile(&metrics, &path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn test_fetches_are_recorded() {
+    let _m = mock("GET", "/metrics-test/page")
+        .with_status(200)
+        .with_body("<html>page</html>")
+        .expect(1)
+        .create();
+    let client = crate::client::create_client().unwrap();
+    let url = format!("{}/metrics-test/page", server_url());
+    let cache_path = crate::cache::get_cache_path(&url);
+    let _ = std::fs::remove_file(&cache_path);
+
+    // Other tests fetch concurrently, so only check that the counters grew
+    let pages = metrics().pages_fetched();
+    let misses = metrics().cache_lookups(CacheResult::Miss);
+    let hits = metrics().cache_lookups(CacheResult::Hit);
+    let ttl = Some(Duration::from_secs(60));
+    assert_eq!(cached_fetch(&client, &url, ttl).await.unwrap(), "<html>page</html>");
+    assert_eq!(cached_fetch(&client, &url, ttl).await.unwrap(), "<html>page</html>");
+    assert!(metrics().pages_fetched() > pages);
+    assert!(metrics().cache_lookups(CacheResult::Miss) > misses);
+    assert!(metrics().cache_lookups(CacheResult::Hit) > hits);
+    assert!(metrics().render().contains("ghtopdep_http_responses_total{code=\"200\"}"));
+    _m.assert();
+
+    std::fs::remove_file(&cache_path).unwrap();
+}
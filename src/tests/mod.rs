@@ -1,9 +1,36 @@
+use crate::dependent::Dependent;
+
+/// A repository dependent with only its name and star count set.
+fn dep(repo: &str, stars: &str) -> Dependent {
+    Dependent {
+        repo: repo.to_string(),
+        stars: stars.to_string(),
+        ..Dependent::default()
+    }
+}
+
+mod batch_tests;
 mod cache_tests;
+mod cli_tests;
 mod client_tests;
 mod config_tests;
 mod dependent_tests;
+mod fetcher_tests;
+mod filter_tests;
+mod fixtures_tests;
 mod github_tests;
+mod graph_tests;
+mod metrics_tests;
 mod output_tests;
+mod progress_tests;
+mod selectors_tests;
+mod serve_tests;
+mod settings_tests;
+mod snapshot_tests;
+mod template_tests;
+mod tree_tests;
+mod trend_tests;
+mod watch_tests;
 
 // Integration tests that use multiple modules together
 mod integration_tests; 
\ No newline at end of file
@@ -1,42 +1,41 @@
 use crate::config::Config;
-use crate::dependent::Dependent;
-use crate::output::display_results;
-use std::str;
+use crate::progress::ProgressMode;
+use crate::dependent::{Dependent, DependentType, TypeSelection};
+use crate::output::{display_results, Column, OutputFormat};
+use crate::github::GITHUB_BASE_URL;
+use crate::template::Template;
+use super::dep;
 
 // Helper function to create a test config
-fn create_test_config(format: &str) -> Config {
+fn create_test_config(format: OutputFormat) -> Config {
     Config {
         owner: "test".to_string(),
         repo: "repo".to_string(),
         top_n: 10,
         max_pages: 1,
         min_stars: 0.0,
-        is_package: false,
-        show_desc: false,
         use_cache: false,
-        output_format: format.to_string(),
+        output_format: format,
+        progress: ProgressMode::Never,
+        quiet: true,
+        ..Config::default()
     }
 }
 
 // Helper function to create test dependents
 fn create_test_dependents() -> Vec<Dependent> {
     vec![
+        dep("user1/repo1", "100"),
         Dependent {
-            repo: "user1/repo1".to_string(),
-            stars: "100".to_string(),
-            description: None,
-        },
-        Dependent {
-            repo: "user2/repo2".to_string(),
-            stars: "200".to_string(),
             description: Some("Test description".to_string()),
+            ..dep("user2/repo2", "200")
         },
     ]
 }
 
 #[test]
 fn test_json_output() {
-    let config = create_test_config("json");
+    let config = create_test_config(OutputFormat::Json);
     let dependents = create_test_dependents();
     
     let result = display_results(
@@ -45,6 +44,7 @@ fn test_json_output() {
         10,
         5,
         20,
+        None,
         1.5,
     );
     
@@ -54,7 +54,7 @@ fn test_json_output() {
 
 #[test]
 fn test_table_output() {
-    let config = create_test_config("table");
+    let config = create_test_config(OutputFormat::Table);
     let dependents = create_test_dependents();
     
     let result = display_results(
@@ -63,6 +63,7 @@ fn test_table_output() {
         10,
         5,
         20,
+        None,
         1.5,
     );
     
@@ -71,7 +72,7 @@ fn test_table_output() {
 
 #[test]
 fn test_text_output() {
-    let config = create_test_config("text");
+    let config = create_test_config(OutputFormat::Text);
     let dependents = create_test_dependents();
     
     let result = display_results(
@@ -80,6 +81,7 @@ fn test_text_output() {
         10,
         5,
         20,
+        None,
         1.5,
     );
     
@@ -88,7 +90,7 @@ fn test_text_output() {
 
 #[test]
 fn test_empty_dependents() {
-    let config = create_test_config("text");
+    let config = create_test_config(OutputFormat::Text);
     let dependents: Vec<Dependent> = vec![];
     
     let result = display_results(
@@ -97,8 +99,102 @@ fn test_empty_dependents() {
         0,
         0,
         0,
+        None,
         1.5,
     );
     
     assert!(result.is_ok());
-} 
\ No newline at end of file
+} 
+
+#[test]
+fn test_parse_column() {
+    assert_eq!("rank".parse::<Column>().unwrap(), Column::Rank);
+    assert_eq!("forks".parse::<Column>().unwrap(), Column::Forks);
+    assert_eq!("last_commit".parse::<Column>().unwrap(), Column::LastCommit);
+    assert!("watchers".parse::<Column>().is_err());
+    assert!("".parse::<Column>().is_err());
+}
+
+#[test]
+fn test_column_values() {
+    let dependents = create_test_dependents();
+    
+    assert_eq!(Column::Rank.value(GITHUB_BASE_URL, 2, &dependents[1]), "2");
+    assert_eq!(Column::Url.value(GITHUB_BASE_URL, 1, &dependents[0]), "https://github.com/user1/repo1");
+    assert_eq!(Column::Forks.value(GITHUB_BASE_URL, 1, &dependents[0]), "");
+    assert_eq!(Column::Description.value(GITHUB_BASE_URL, 2, &dependents[1]), "Test description");
+}
+
+#[test]
+fn test_custom_columns_and_template_output() {
+    let dependents = create_test_dependents();
+    
+    let mut config = create_test_config(OutputFormat::Table);
+    config.columns = Some(vec![Column::Rank, Column::Repo, Column::Description]);
+    assert!(display_results(&config, &dependents, 10, 5, 20, None, 1.5).is_ok());
+    
+    let mut config = create_test_config(OutputFormat::Text);
+    config.columns = Some(vec![Column::Repo, Column::Stars]);
+    assert!(display_results(&config, &dependents, 10, 5, 20, None, 1.5).is_ok());
+    
+    config.template = Some("{rank}: {repo}".parse::<Template>().unwrap());
+    assert!(display_results(&config, &dependents, 10, 5, 20, None, 1.5).is_ok());
+}
+
+#[test]
+fn test_csv_output() {
+    use crate::output::csv_line;
+
+    let line = csv_line(["plain".to_string(), "a, b".to_string(), "say \"hi\"".to_string(), String::new()]);
+    assert_eq!(line, "plain,\"a, b\",\"say \"\"hi\"\"\",");
+
+    let dependents = create_test_dependents();
+    assert_eq!(Column::Archived.value(GITHUB_BASE_URL, 1, &dependents[0]), "");
+    let config = create_test_config(OutputFormat::Csv);
+    assert!(display_results(&config, &dependents, 10, 5, 20, None, 1.5).is_ok());
+}
+
+#[test]
+fn test_dependent_type_sections_and_merge() {
+    use crate::github::{merge_crawls, Crawl, HeaderCounts};
+    use crate::output::{display_sections, Section};
+
+    let mut packages = create_test_dependents();
+    packages[0].stars = "150".to_string();
+    for dep in &mut packages {
+        dep.dependent_type = DependentType::Package;
+    }
+    assert_eq!(Column::Type.value(GITHUB_BASE_URL, 1, &packages[0]), "package");
+    let json = serde_json::to_value(&packages[0]).unwrap();
+    assert_eq!(json["type"], "package");
+
+    let crawl = |dependents: Vec<Dependent>| Crawl {
+        total_repos_count: dependents.len(),
+        max_deps: dependents.len(),
+        header: HeaderCounts::default(),
+        matched_count: dependents.len(),
+        unique_upstreams: None,
+        dependents,
+    };
+    let merged = merge_crawls(vec![crawl(create_test_dependents()), crawl(packages.clone())]);
+    let stars: Vec<&str> = merged.dependents.iter().map(|d| d.stars.as_str()).collect();
+    assert_eq!(stars, vec!["200", "200", "150", "100"]);
+    assert_eq!(merged.total_repos_count, 4);
+    assert_eq!(merged.matched_count, 4);
+
+    let mut config = create_test_config(OutputFormat::Json);
+    config.type_selection = TypeSelection::Both;
+    let type_configs = config.type_configs();
+    let crawls = vec![crawl(create_test_dependents()), crawl(packages)];
+    let sections: Vec<Section> = type_configs.into_iter().zip(crawls)
+        .map(|(type_config, crawl)| Section {
+            top_dependents: crawl.dependents.clone(),
+            config: type_config,
+            crawl,
+        })
+        .collect();
+    assert_eq!(sections[1].config.section_name(), "packages");
+    assert!(display_sections(&config, &sections, 1.0).is_ok());
+    config.output_format = OutputFormat::Table;
+    assert!(display_sections(&config, &sections, 1.0).is_ok());
+}
@@ -0,0 +1,42 @@
+use clap::ValueEnum;
+use crate::progress::{Progress, ProgressMode};
+
+#[test]
+fn test_progress_mode_from_str() {
+    assert_eq!(ProgressMode::from_str("auto", false).unwrap(), ProgressMode::Auto);
+    assert_eq!(ProgressMode::from_str("always", false).unwrap(), ProgressMode::Always);
+    assert_eq!(ProgressMode::from_str("never", false).unwrap(), ProgressMode::Never);
+    assert_eq!(ProgressMode::from_str("plain", false).unwrap(), ProgressMode::Plain);
+    assert!(ProgressMode::from_str("bar", false).is_err());
+}
+
+#[test]
+fn test_progress_mode_resolve() {
+    // Explicit modes are never changed by terminal detection
+    assert_eq!(ProgressMode::Never.resolve(), ProgressMode::Never);
+    assert_eq!(ProgressMode::Plain.resolve(), ProgressMode::Plain);
+    assert_ne!(ProgressMode::Auto.resolve(), ProgressMode::Auto);
+}
+
+#[test]
+fn test_plain_progress_tracks_dependents() {
+    let mut progress = Progress::new(ProgressMode::Plain, 60);
+    progress.set_position(30);
+    // Lines are printed at most every few seconds until the crawl finishes
+    assert_eq!(progress.last_line(), None);
+    progress.finish();
+    assert_eq!(progress.last_line(), Some("Progress: 30/60 dependents (50%)"));
+    progress.set_position(75);
+    progress.finish();
+    assert_eq!(progress.last_line(), Some("Progress: 75/60 dependents (100%)"));
+
+    let mut progress = Progress::new(ProgressMode::Plain, 0);
+    progress.set_position(12);
+    progress.finish();
+    assert_eq!(progress.last_line(), Some("Progress: 12 dependents"));
+
+    let mut progress = Progress::new(ProgressMode::Never, 0);
+    progress.set_position(10);
+    progress.finish();
+    assert_eq!(progress.last_line(), None);
+}
@@ -0,0 +1,53 @@
+use crate::cli::SelectorsAction;
+use crate::commands::run_selectors;
+use crate::selectors::{SelectorProfile, Selectors};
+
+#[test]
+fn test_selector_profile_overrides() {
+    let profile = SelectorProfile::parse("dependent-row = \"li.dependent\"\nstars = \"span.stars\"\n", "test").unwrap();
+    assert_eq!(profile.dependent_row, "li.dependent");
+    assert_eq!(profile.stars, "span.stars");
+    assert_eq!(profile.next_page, SelectorProfile::default().next_page);
+
+    let default = SelectorProfile::default();
+    assert_eq!(SelectorProfile::parse(&default.to_toml(), "test").unwrap(), default);
+
+    let selectors = Selectors::compile(profile).unwrap();
+    let html = scraper::Html::parse_document(r#"<ul><li class="dependent"><span class="stars">5</span></li></ul>"#);
+    assert_eq!(html.select(&selectors.dependent_row).count(), 1);
+    assert_eq!(selectors.entries().len(), 23);
+
+    let profile = SelectorProfile::parse("archived-notice = \"div.archived\"", "test").unwrap();
+    let selectors = Selectors::compile(profile).unwrap();
+    let html = scraper::Html::parse_document(r#"<div class="archived">Public archive</div>"#);
+    assert_eq!(html.select(&selectors.archived_notice).count(), 1);
+    assert!(selectors.entries().iter().any(|(key, selector, _)| *key == "archived-notice" && *selector == "div.archived"));
+}
+
+#[test]
+fn test_invalid_selector_profiles() {
+    let err = SelectorProfile::parse("star = \"span\"", "selectors.toml").unwrap_err().to_string();
+    assert!(err.contains("selectors.toml") && err.contains("unknown field"), "{}", err);
+
+    let profile = SelectorProfile::parse("next-page = \"a >> b\"", "test").unwrap();
+    let err = Selectors::compile(profile).unwrap_err().to_string();
+    assert!(err.contains("next-page") && err.contains("a >> b"), "{}", err);
+
+    let path = std::env::temp_dir().join("ghtopdep_selectors_test.toml");
+    std::fs::write(&path, "stars = \"\"").unwrap();
+    let err = Selectors::load(&path).unwrap_err().to_string();
+    assert!(err.contains(&path.display().to_string()) && err.contains("stars"), "{}", err);
+    std::fs::remove_file(&path).unwrap();
+    assert!(Selectors::load(&path).is_err());
+}
+
+#[test]
+fn test_selectors_test_command() {
+    let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/fixtures/dependents_page.html");
+    assert!(run_selectors(&SelectorsAction::Test { html_file: fixture }).is_ok());
+
+    let path = std::env::temp_dir().join("ghtopdep_selectors_test_page.html");
+    std::fs::write(&path, "<html><body><div class=\"dependents-v2\"></div></body></html>").unwrap();
+    assert!(run_selectors(&SelectorsAction::Test { html_file: path.clone() }).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
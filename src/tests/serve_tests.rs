@@ -0,0 +1,90 @@
+use std::time::Duration;
+use hyper::{Method, StatusCode};
+use mockito::{mock, server_url};
+
+use crate::client::ClientOptions;
+use crate::config::Config;
+use crate::github::Crawler;
+use crate::serve::DependentsService;
+
+const PAGE: &str = r#"
+<div class="flex-items-center"><a class="text-bold" href="/serve-a/big">serve-a/big</a><div><span>120</span></div></div>
+<div class="flex-items-center"><a class="text-bold" href="/serve-b/mid">serve-b/mid</a><div><span>40</span></div></div>
+<div class="flex-items-center"><a class="text-bold" href="/serve-c/small">serve-c/small</a><div><span>2</span></div></div>
+"#;
+
+fn service() -> std::sync::Arc<DependentsService> {
+    let config = Config {
+        base_url: server_url(),
+        use_cache: false,
+        max_pages: 1,
+        ..Config::default()
+    };
+    let crawler = Crawler::new(&ClientOptions::default()).unwrap();
+    DependentsService::new(crawler, config, Duration::from_secs(3600)).0
+}
+
+#[tokio::test]
+async fn test_invalid_requests() {
+    let service = service();
+    for (path, status) in [
+        ("/", StatusCode::NOT_FOUND),
+        ("/v1/owner/repo", StatusCode::NOT_FOUND),
+        ("/v1/owner/repo/dependents?type=both", StatusCode::BAD_REQUEST),
+        ("/v1/owner/repo/dependents?min_stars=-1", StatusCode::BAD_REQUEST),
+        ("/v1/owner/repo/dependents?limit=0", StatusCode::BAD_REQUEST),
+    ] {
+        let (actual, body) = service.handle(&Method::GET, path).await;
+        assert_eq!(actual, status, "{}", path);
+        assert!(body["error"].is_string());
+    }
+    let (status, _) = service.handle(&Method::POST, "/v1/owner/repo/dependents").await;
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+}
+
+#[tokio::test]
+async fn test_concurrent_requests_share_one_crawl() {
+    let page = mock("GET", "/serve-owner/lib/network/dependents?dependent_type=REPOSITORY")
+        .with_status(200)
+        .with_body(PAGE)
+        .expect(1)
+        .create();
+    let service = service();
+
+    let (first, second) = tokio::join!(
+        service.handle(&Method::GET, "/v1/serve-owner/lib/dependents?min_stars=10"),
+        service.handle(&Method::GET, "/v1/Serve-Owner/lib/dependents?limit=1"),
+    );
+    assert_eq!(first.0, StatusCode::OK);
+    assert_eq!(first.1["stats"]["repositories_with_stars"], 2);
+    assert_eq!(first.1["dependents"][1]["repo"], "serve-b/mid");
+    assert_eq!(second.1["dependents"].as_array().unwrap().len(), 1);
+    assert_eq!(second.1["stats"]["total_repositories"], 3);
+
+    let (status, _) = service.handle(&Method::GET, "/v1/serve-owner/lib/dependents").await;
+    assert_eq!(status, StatusCode::OK);
+    page.assert();
+}
+
+#[tokio::test]
+async fn test_min_stars_below_the_default() {
+    let _page = mock("GET", "/serve-floor/lib/network/dependents?dependent_type=REPOSITORY")
+        .with_status(200)
+        .with_body(PAGE)
+        .create();
+    let config = Config {
+        base_url: server_url(),
+        use_cache: false,
+        max_pages: 1,
+        min_stars: 50.0,
+        ..Config::default()
+    };
+    let crawler = Crawler::new(&ClientOptions::default()).unwrap();
+    let service = DependentsService::new(crawler, config, Duration::from_secs(3600)).0;
+
+    let (_, default) = service.handle(&Method::GET, "/v1/serve-floor/lib/dependents").await;
+    assert_eq!(default["stats"]["repositories_with_stars"], 1);
+    let (_, lower) = service.handle(&Method::GET, "/v1/serve-floor/lib/dependents?min_stars=1").await;
+    assert_eq!(lower["stats"]["repositories_with_stars"], 3);
+    assert_eq!(lower["dependents"][2]["repo"], "serve-c/small");
+}
@@ -0,0 +1,122 @@
+use crate::output::OutputFormat;
+use crate::settings::{resolve_settings, ConfigFile, Settings};
+
+const USER_CONFIG: &str = r#"
+[defaults]
+format = "text"
+rows = 20
+cache-ttl = 48
+
+[profiles.ci]
+format = "json"
+minstar = 10.0
+"#;
+
+const PROJECT_CONFIG: &str = r#"
+[defaults]
+rows = 5
+base-url = "https://github.example.com"
+
+[profiles.ci]
+rows = 50
+"#;
+
+#[test]
+fn test_parse_config_file() {
+    let file = ConfigFile::parse(USER_CONFIG, "config.toml").unwrap();
+    assert_eq!(file.defaults.format, Some(OutputFormat::Text));
+    assert_eq!(file.defaults.rows, Some(20));
+    assert_eq!(file.defaults.cache_ttl, Some(48));
+    assert_eq!(file.profiles["ci"].minstar, Some(10.0));
+}
+
+#[test]
+fn test_invalid_config_files() {
+    assert!(ConfigFile::parse("[defaults]\nrowz = 5", "config.toml").is_err());
+    assert!(ConfigFile::parse("[defaults]\nformat = \"xml\"", "config.toml").is_err());
+    assert!(ConfigFile::parse("[defaults]\nrows = 0", "config.toml").is_err());
+    assert!(ConfigFile::parse("[profiles.ci]\nminstar = -1.0", "config.toml").is_err());
+    assert!(ConfigFile::parse("[defaults]\nbase-url = \"github.com\"", "config.toml").is_err());
+}
+
+#[test]
+fn test_resolve_precedence() {
+    let user = ConfigFile::parse(USER_CONFIG, "config.toml").unwrap();
+    let project = ConfigFile::parse(PROJECT_CONFIG, ".ghtopdep.toml").unwrap();
+
+    // Project defaults override user defaults
+    let settings = resolve_settings(Settings::default(), Settings::default(), Some(&project), Some(&user), None).unwrap();
+    assert_eq!(settings.rows, Some(5));
+    assert_eq!(settings.format, Some(OutputFormat::Text));
+    assert_eq!(settings.cache_ttl, Some(48));
+    assert_eq!(settings.base_url.as_deref(), Some("https://github.example.com"));
+    assert_eq!(settings.minstar, None);
+
+    // A selected profile overrides the defaults of both files
+    let settings = resolve_settings(Settings::default(), Settings::default(), Some(&project), Some(&user), Some("ci")).unwrap();
+    assert_eq!(settings.rows, Some(50));
+    assert_eq!(settings.format, Some(OutputFormat::Json));
+    assert_eq!(settings.minstar, Some(10.0));
+
+    // Environment overrides files, flags override the environment
+    let env = Settings::from_env_vars(vec![
+        ("GHTOPDEP_ROWS".to_string(), "7".to_string()),
+        ("GHTOPDEP_CACHE_TTL".to_string(), "1".to_string()),
+        ("GHTOPDEP_PROFILE".to_string(), "ci".to_string()),
+        ("HOME".to_string(), "/home/user".to_string()),
+    ]).unwrap();
+    let flags = Settings { cache_ttl: Some(0), ..Settings::default() };
+    let settings = resolve_settings(flags, env, Some(&project), Some(&user), Some("ci")).unwrap();
+    assert_eq!(settings.rows, Some(7));
+    assert_eq!(settings.cache_ttl, Some(0));
+    assert_eq!(settings.format, Some(OutputFormat::Json));
+}
+
+#[test]
+fn test_unknown_profile() {
+    let user = ConfigFile::parse(USER_CONFIG, "config.toml").unwrap();
+    let result = resolve_settings(Settings::default(), Settings::default(), None, Some(&user), Some("nightly"));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_invalid_env_vars() {
+    assert!(Settings::from_env_vars(vec![("GHTOPDEP_ROWS".to_string(), "many".to_string())]).is_err());
+    assert!(Settings::from_env_vars(vec![("GHTOPDEP_FORMAT".to_string(), "xml".to_string())]).is_err());
+    assert!(Settings::from_env_vars(vec![("GHTOPDEP_MINSTAR".to_string(), "-5".to_string())]).is_err());
+
+    let settings = Settings::from_env_vars(vec![
+        ("GHTOPDEP_FORMAT".to_string(), "json".to_string()),
+        ("GHTOPDEP_TOKEN_COMMAND".to_string(), "gh auth token".to_string()),
+    ]).unwrap();
+    assert_eq!(settings.format, Some(OutputFormat::Json));
+    assert_eq!(settings.token_command.as_deref(), Some("gh auth token"));
+}
+
+#[test]
+fn test_network_settings() {
+    use std::path::PathBuf;
+
+    let file = ConfigFile::parse(r#"
+[defaults]
+proxy = "http://proxy.corp.example:3128"
+ca-cert = "/etc/ssl/corp-ca.pem"
+connect-timeout = 5
+"#, "network.toml").unwrap();
+    assert_eq!(file.defaults.proxy.as_deref(), Some("http://proxy.corp.example:3128"));
+    assert_eq!(file.defaults.ca_cert, Some(PathBuf::from("/etc/ssl/corp-ca.pem")));
+    assert_eq!(file.defaults.connect_timeout, Some(5));
+
+    let env = Settings::from_env_vars(vec![
+        ("GHTOPDEP_TIMEOUT".to_string(), "120".to_string()),
+        ("GHTOPDEP_USER_AGENT".to_string(), "ghtopdep-ci".to_string()),
+        ("GHTOPDEP_CLIENT_CERT".to_string(), "/etc/ssl/me.pem".to_string()),
+    ]).unwrap();
+    assert_eq!(env.timeout, Some(120));
+    assert_eq!(env.user_agent.as_deref(), Some("ghtopdep-ci"));
+    assert_eq!(env.client_cert, Some(PathBuf::from("/etc/ssl/me.pem")));
+
+    assert!(ConfigFile::parse("[defaults]\nproxy = \"proxy.corp.example:3128\"", "bad.toml").is_err());
+    assert!(Settings::from_env_vars(vec![("GHTOPDEP_TIMEOUT".to_string(), "0".to_string())]).is_err());
+    assert!(Settings::from_env_vars(vec![("GHTOPDEP_CONNECT_TIMEOUT".to_string(), "soon".to_string())]).is_err());
+}
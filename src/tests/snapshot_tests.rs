@@ -0,0 +1,92 @@
+use std::time::{Duration, UNIX_EPOCH};
+use crate::config::Config;
+use crate::dependent::Dependent;
+use crate::github::{Crawl, HeaderCounts};
+use crate::snapshot::{diff_snapshots, list_snapshots, resolve_snapshot, Snapshot};
+use super::dep;
+
+fn snapshot(taken_at: u64, dependents: Vec<Dependent>) -> Snapshot {
+    let config = Config {
+        owner: "Owner".to_string(),
+        repo: "lib".to_string(),
+        ..Config::default()
+    };
+    let crawl = Crawl {
+        total_repos_count: dependents.len(),
+        max_deps: dependents.len(),
+        header: HeaderCounts::default(),
+        matched_count: dependents.len(),
+        unique_upstreams: None,
+        dependents,
+    };
+    Snapshot::new(&config, &crawl, UNIX_EPOCH + Duration::from_secs(taken_at))
+}
+
+#[test]
+fn test_save_list_and_resolve() {
+    let root = std::env::temp_dir().join("ghtopdep_snapshot_tests");
+    let _ = std::fs::remove_dir_all(&root);
+
+    let second = snapshot(1_700_000_100, vec![dep("a/b", "5")]).save(&root).unwrap();
+    let first = snapshot(1_700_000_000, vec![dep("a/b", "3")]).save(&root).unwrap();
+    assert!(first.ends_with("owner/lib/repository/1700000000.json"));
+
+    let config = Config {
+        owner: "owner".to_string(),
+        repo: "lib".to_string(),
+        ..Config::default()
+    };
+    assert_eq!(list_snapshots(&root, &config).unwrap(), vec![first.clone(), second]);
+    assert_eq!(resolve_snapshot(&root, &config, "1700000000").unwrap(), first);
+    assert_eq!(resolve_snapshot(&root, &config, &first.display().to_string()).unwrap(), first);
+    assert!(resolve_snapshot(&root, &config, "42").is_err());
+
+    let loaded = Snapshot::load(&first).unwrap();
+    assert_eq!(loaded.dependents[0].stars, "3");
+    assert_eq!(loaded.taken_at_display(), "2023-11-14T22:13:20Z");
+
+    let packages = Config { is_package: true, ..config };
+    assert!(list_snapshots(&root, &packages).unwrap().is_empty());
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_snapshots_saved_in_the_same_second() {
+    let root = std::env::temp_dir().join("ghtopdep_snapshot_same_second_tests");
+    let _ = std::fs::remove_dir_all(&root);
+
+    let paths: Vec<_> = ["1", "2", "3"].iter()
+        .map(|stars| snapshot(1_700_000_000, vec![dep("a/b", stars)]).save(&root).unwrap())
+        .collect();
+    assert!(paths[0].ends_with("1700000000.json"));
+    assert!(paths[1].ends_with("1700000000-2.json"));
+    assert!(paths[2].ends_with("1700000000-3.json"));
+
+    let config = Config {
+        owner: "owner".to_string(),
+        repo: "lib".to_string(),
+        ..Config::default()
+    };
+    assert_eq!(list_snapshots(&root, &config).unwrap(), paths);
+    let latest = resolve_snapshot(&root, &config, "1700000000-3").unwrap();
+    assert_eq!(Snapshot::load(&latest).unwrap().dependents[0].stars, "3");
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_diff_snapshots() {
+    let old = snapshot(1, vec![dep("x/kept", "100"), dep("x/grew", "1k"), dep("x/gone", "7"), dep("x/gone-big", "2k")]);
+    let new = snapshot(2, vec![dep("x/kept", "100"), dep("x/grew", "1.5k"), dep("x/new", "3"), dep("x/new-big", "40")]);
+
+    let diff = diff_snapshots(&old, &new);
+    assert_eq!((diff.from, diff.to), (1, 2));
+
+    let added: Vec<&str> = diff.added.iter().map(|d| d.repo.as_str()).collect();
+    assert_eq!(added, vec!["x/new-big", "x/new"]);
+    let removed: Vec<&str> = diff.removed.iter().map(|d| d.repo.as_str()).collect();
+    assert_eq!(removed, vec!["x/gone-big", "x/gone"]);
+
+    assert_eq!(diff.star_changes.len(), 1);
+    assert_eq!(diff.star_changes[0].repo, "x/grew");
+    assert_eq!(diff.star_changes[0].delta, 500.0);
+}
@@ -0,0 +1,43 @@
+use crate::dependent::Dependent;
+use crate::github::GITHUB_BASE_URL;
+use crate::template::Template;
+
+fn test_dependent() -> Dependent {
+    Dependent {
+        forks: Some("34".to_string()),
+        description: Some("A test repository".to_string()),
+        ..super::dep("user1/repo1", "1.2k")
+    }
+}
+
+#[test]
+fn test_render_template() {
+    let template: Template = "{rank}. {repo} ({stars} stars, {forks} forks)".parse().unwrap();
+    assert_eq!(template.render(GITHUB_BASE_URL, 1, &test_dependent()), "1. user1/repo1 (1.2k stars, 34 forks)");
+
+    let template: Template = "{url}".parse().unwrap();
+    assert_eq!(template.render(GITHUB_BASE_URL, 2, &test_dependent()), "https://github.com/user1/repo1");
+}
+
+#[test]
+fn test_template_escapes() {
+    let template: Template = r"{{{repo}}}\t{stars}\n   {description}".parse().unwrap();
+    assert_eq!(
+        template.render(GITHUB_BASE_URL, 1, &test_dependent()),
+        "{user1/repo1}\t1.2k\n   A test repository"
+    );
+}
+
+#[test]
+fn test_template_missing_values() {
+    let dep = super::dep("user2/repo2", "5");
+    let template: Template = "{repo}|{forks}|{description}".parse().unwrap();
+    assert_eq!(template.render(GITHUB_BASE_URL, 3, &dep), "user2/repo2||");
+}
+
+#[test]
+fn test_invalid_templates() {
+    assert!("{unknown}".parse::<Template>().is_err());
+    assert!("{repo".parse::<Template>().is_err());
+    assert!("repo}".parse::<Template>().is_err());
+}
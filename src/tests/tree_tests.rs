@@ -0,0 +1,104 @@
+use mockito::{mock, server_url};
+
+use crate::client::ClientOptions;
+use crate::config::Config;
+use crate::github::Crawler;
+use crate::tree::build_tree;
+
+fn dependents_page(repos: &[&str]) -> String {
+    repos.iter()
+        .map(|repo| format!(
+            r#"<div class="flex-items-center"><span><a class="text-bold" href="/{0}">{0}</a></span><div><span>5</span></div></div>"#,
+            repo
+        ))
+        .collect()
+}
+
+fn tree_config(depth: usize, budget: usize) -> Config {
+    Config {
+        owner: "tree-root".to_string(),
+        repo: "lib".to_string(),
+        base_url: server_url(),
+        use_cache: false,
+        quiet: true,
+        max_pages: 1,
+        depth,
+        expand_min_stars: 0.0,
+        crawl_budget: budget,
+        ..Config::default()
+    }
+}
+
+#[tokio::test]
+async fn test_build_tree() {
+    let _root = mock("GET", "/tree-root/lib/network/dependents?dependent_type=REPOSITORY")
+        .with_status(200)
+        .with_body(dependents_page(&["tree-a/one", "tree-b/two"]))
+        .create();
+    let _one = mock("GET", "/tree-a/one/network/dependents?dependent_type=REPOSITORY")
+        .with_status(200)
+        .with_body(dependents_page(&["tree-c/three", "tree-b/two"]))
+        .create();
+    let _two = mock("GET", "/tree-b/two/network/dependents?dependent_type=REPOSITORY")
+        .with_status(200)
+        .with_body(dependents_page(&["tree-root/lib"]))
+        .create();
+    let crawler = Crawler::new(&ClientOptions::default()).unwrap();
+
+    let tree = build_tree(&crawler, &tree_config(2, 10)).await.unwrap();
+    assert_eq!(tree.crawls, 3);
+    assert!(!tree.truncated);
+    let mut nodes: Vec<(&str, usize)> = tree.nodes.iter()
+        .map(|node| (node.dependent.repo.as_str(), node.depth))
+        .collect();
+    nodes.sort();
+    assert_eq!(nodes, vec![("tree-a/one", 1), ("tree-b/two", 1), ("tree-c/three", 2)]);
+    // Dependents seen before get an edge but no new node
+    assert_eq!(tree.edges.len(), 5);
+    assert_eq!(tree.children("tree-a/one").len(), 2);
+
+    let tree = build_tree(&crawler, &tree_config(2, 2)).await.unwrap();
+    assert_eq!(tree.crawls, 2);
+    assert!(tree.truncated);
+
+    let tree = build_tree(&crawler, &tree_config(1, 10)).await.unwrap();
+    assert_eq!(tree.crawls, 1);
+    assert_eq!(tree.nodes.len(), 2);
+}
+
+#[test]
+fn test_merge_trees() {
+    use crate::dependent::{Dependent, DependentType};
+    use crate::github::HeaderCounts;
+    use crate::tree::{DependencyTree, TreeEdge, TreeNode};
+
+    let tree = |dependent_type: DependentType, repo: &str, stars: &str, depth: usize| DependencyTree {
+        root: "owner/lib".to_string(),
+        root_total: 3,
+        nodes: vec![TreeNode {
+            dependent: Dependent { dependent_type, ..super::dep(repo, stars) },
+            depth,
+            total_dependents: None,
+        }],
+        edges: vec![TreeEdge {
+            dependency: "owner/lib".to_string(),
+            dependent: repo.to_string(),
+            dependent_type,
+            package: None,
+        }],
+        crawls: 1,
+        truncated: depth > 1,
+        header: HeaderCounts::default(),
+    };
+
+    let merged = tree(DependentType::Repository, "a/app", "1", 2)
+        .merge(tree(DependentType::Package, "a/app", "1", 1))
+        .merge(tree(DependentType::Package, "b/pkg", "1.2k", 1));
+    assert_eq!(merged.nodes.len(), 2);
+    assert_eq!(merged.node("a/app").unwrap().depth, 1);
+    assert_eq!(merged.edges.len(), 3);
+    assert_eq!((merged.root_total, merged.crawls, merged.truncated), (9, 3, true));
+    // Children stay most starred first across merged trees
+    let children: Vec<&str> = merged.children("owner/lib").iter().map(|edge| edge.dependent.as_str()).collect();
+    assert_eq!(children, vec!["b/pkg", "a/app", "a/app"]);
+}
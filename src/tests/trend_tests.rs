@@ -0,0 +1,51 @@
+use std::time::{Duration, UNIX_EPOCH};
+use crate::github::HeaderCounts;
+use crate::trend::{append_point, load_points, record_point, sparkline, TrendPoint};
+
+#[test]
+fn test_append_and_load_points() {
+    let root = std::env::temp_dir().join("ghtopdep_trend_tests");
+    let _ = std::fs::remove_dir_all(&root);
+
+    let counts = HeaderCounts { repositories: Some(120), packages: None };
+    let later = TrendPoint::new(counts, UNIX_EPOCH + Duration::from_secs(200));
+    let earlier = TrendPoint { taken_at: 100, repositories: Some(100), packages: Some(3) };
+    append_point(&root, "Owner", "Lib", &later).unwrap();
+    append_point(&root, "owner", "lib", &earlier).unwrap();
+
+    assert_eq!(load_points(&root, "owner", "lib").unwrap(), vec![earlier, later]);
+    assert!(load_points(&root, "owner", "other").unwrap().is_empty());
+
+    std::fs::write(root.join("owner").join("lib.jsonl"), "{\"taken_at\": 1}\nnot json\n").unwrap();
+    let err = load_points(&root, "owner", "lib").unwrap_err().to_string();
+    assert!(err.contains("lib.jsonl:2"), "{}", err);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_cached_counts_are_recorded_once() {
+    let root = std::env::temp_dir().join("ghtopdep_trend_cache_tests");
+    let _ = std::fs::remove_dir_all(&root);
+    let ttl = Some(Duration::from_secs(3600));
+    let point = |taken_at, repositories| TrendPoint { taken_at, repositories: Some(repositories), packages: None };
+
+    assert!(record_point(&root, "owner", "lib", &point(1000, 120), ttl).unwrap());
+    // A repeat run within the cache TTL may have read the same page
+    assert!(!record_point(&root, "owner", "lib", &point(1600, 120), ttl).unwrap());
+    // New counts, an expired cache and uncached runs are recorded
+    assert!(record_point(&root, "owner", "lib", &point(1700, 121), ttl).unwrap());
+    assert!(record_point(&root, "owner", "lib", &point(5400, 121), ttl).unwrap());
+    assert!(record_point(&root, "owner", "lib", &point(5500, 121), None).unwrap());
+
+    let taken_at: Vec<u64> = load_points(&root, "owner", "lib").unwrap().iter().map(|point| point.taken_at).collect();
+    assert_eq!(taken_at, vec![1000, 1700, 5400, 5500]);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_sparkline() {
+    assert_eq!(sparkline(&[Some(0), Some(7), Some(14)]), "▁▄█");
+    assert_eq!(sparkline(&[Some(5), None, Some(5)]), "▁ ▁");
+    assert_eq!(sparkline(&[None, None]), "  ");
+    assert_eq!(sparkline(&[]), "");
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+use crate::client::{create_client, ClientOptions};
+use crate::snapshot::{SnapshotDiff, StarChange};
+use crate::watch::{send_webhook, watch_events, webhook_client, WatchEvent, WatchOptions, WebhookPayload};
+use super::dep;
+
+fn options(min_stars: f64, thresholds: Vec<f64>) -> WatchOptions {
+    WatchOptions {
+        interval: Duration::from_secs(3600),
+        min_stars,
+        thresholds,
+        webhook: None,
+        json: false,
+        metrics_bind: None,
+    }
+}
+
+fn change(repo: &str, from: &str, to: &str) -> StarChange {
+    StarChange {
+        repo: repo.to_string(),
+        from: from.to_string(),
+        to: to.to_string(),
+        delta: 0.0,
+    }
+}
+
+fn sample_diff() -> SnapshotDiff {
+    SnapshotDiff {
+        from: 1,
+        to: 2,
+        added: vec![
+            dep("x/big", "1.2k"),
+            dep("x/small", "4"),
+        ],
+        removed: vec![],
+        star_changes: vec![
+            change("x/rising", "90", "1.1k"),
+            change("x/steady", "120", "150"),
+            change("x/falling", "1.1k", "90"),
+        ],
+    }
+}
+
+#[test]
+fn test_watch_events() {
+    let events = watch_events(&sample_diff(), &options(100.0, vec![100.0, 1000.0]));
+    assert_eq!(events, vec![
+        WatchEvent::NewDependent { repo: "x/big".to_string(), stars: "1.2k".to_string() },
+        WatchEvent::StarThreshold {
+            repo: "x/rising".to_string(), threshold: 100.0, from: "90".to_string(), to: "1.1k".to_string(),
+        },
+        WatchEvent::StarThreshold {
+            repo: "x/rising".to_string(), threshold: 1000.0, from: "90".to_string(), to: "1.1k".to_string(),
+        },
+    ]);
+
+    let events = watch_events(&sample_diff(), &options(0.0, vec![]));
+    assert_eq!(events.len(), 2);
+}
+
+#[tokio::test]
+async fn test_send_webhook() {
+    use mockito::{mock, server_url, Matcher};
+
+    let hook = mock("POST", "/hooks/dependents")
+        .match_header("content-type", "application/json")
+        .match_body(Matcher::Json(serde_json::json!({
+            "target": "owner/lib",
+            "checked_at": "2023-11-14T22:13:20Z",
+            "events": [{"event": "new_dependent", "repo": "x/big", "stars": "1.2k"}]
+        })))
+        .with_status(204)
+        .create();
+    let _failing = mock("POST", "/hooks/broken").with_status(500).create();
+
+    let events = vec![WatchEvent::NewDependent { repo: "x/big".to_string(), stars: "1.2k".to_string() }];
+    let payload = WebhookPayload {
+        target: "owner/lib",
+        checked_at: "2023-11-14T22:13:20Z".to_string(),
+        events: &events,
+    };
+    let client = create_client().unwrap();
+
+    send_webhook(&client, &format!("{}/hooks/dependents", server_url()), &payload).await.unwrap();
+    hook.assert();
+    assert!(send_webhook(&client, &format!("{}/hooks/broken", server_url()), &payload).await.is_err());
+}
+
+#[tokio::test]
+async fn test_webhook_client_leaves_out_the_token() {
+    use mockito::{mock, server_url, Matcher};
+
+    let hook = mock("POST", "/hooks/tokenless")
+        .match_header("authorization", Matcher::Missing)
+        .match_header("user-agent", "ghtopdep-test")
+        .with_status(204)
+        .create();
+
+    let events = vec![WatchEvent::NewDependent { repo: "x/big".to_string(), stars: "1.2k".to_string() }];
+    let payload = WebhookPayload {
+        target: "owner/lib",
+        checked_at: "2023-11-14T22:13:20Z".to_string(),
+        events: &events,
+    };
+    let client = webhook_client(&ClientOptions {
+        token: Some("secret".to_string()),
+        user_agent: Some("ghtopdep-test".to_string()),
+        ..ClientOptions::default()
+    }).unwrap();
+
+    send_webhook(&client, &format!("{}/hooks/tokenless", server_url()), &payload).await.unwrap();
+    hook.assert();
+}
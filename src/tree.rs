@@ -0,0 +1,214 @@
+use std::collections::{HashMap, HashSet};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::dependent::{Dependent, DependentType, convert_stars_to_number};
+use crate::error::AppError;
+use crate::github::{Crawler, HeaderCounts};
+use crate::progress::ProgressMode;
+use crate::snapshot::save_crawl;
+use crate::trend::record_counts;
+
+/// A repository reached by the reverse-dependency crawl.
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeNode {
+    #[serde(flatten)]
+    pub dependent: Dependent,
+    /// Levels below the root, 1 for direct dependents
+    pub depth: usize,
+    /// Dependents count from the page header, set when the node was crawled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_dependents: Option<usize>,
+}
+
+/// `dependent` depends on `dependency`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeEdge {
+    pub dependency: String,
+    pub dependent: String,
+    #[serde(rename = "type")]
+    pub dependent_type: DependentType,
+    /// The package of `dependency` the dependent uses, when one was selected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+}
+
+/// The dependents of a repository, their dependents and so on. Every
+/// repository is a node once; a dependent of several crawled repositories
+/// has an edge from each.
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyTree {
+    /// The starting repository as `owner/repo`
+    pub root: String,
+    /// Dependents count of the root from the page header
+    pub root_total: usize,
+    /// Nodes in the order they were reached, shallowest first
+    pub nodes: Vec<TreeNode>,
+    pub edges: Vec<TreeEdge>,
+    /// Dependents lists crawled, including the root's
+    pub crawls: usize,
+    /// Whether the budget ran out before every eligible node was crawled
+    pub truncated: bool,
+    /// Header counts of the root's dependents page
+    #[serde(skip)]
+    pub header: HeaderCounts,
+}
+
+impl DependencyTree {
+    pub fn node(&self, repo: &str) -> Option<&TreeNode> {
+        self.nodes.iter().find(|node| node.dependent.repo == repo)
+    }
+
+    /// Edges to the dependents of `repo`, most starred first.
+    pub fn children(&self, repo: &str) -> Vec<&TreeEdge> {
+        let stars = |edge: &TreeEdge| self.node(&edge.dependent)
+            .map_or(0.0, |node| convert_stars_to_number(&node.dependent.stars));
+        let mut children: Vec<&TreeEdge> = self.edges.iter().filter(|edge| edge.dependency == repo).collect();
+        // Merged trees append their edges after the ones already there
+        children.sort_by(|a, b| stars(b).total_cmp(&stars(a)));
+        children
+    }
+
+    /// Adds the nodes and edges of another tree of the same root, e.g. of
+    /// another dependent type or package.
+    pub fn merge(mut self, other: DependencyTree) -> DependencyTree {
+        for node in other.nodes {
+            match self.nodes.iter_mut().find(|existing| existing.dependent.repo == node.dependent.repo) {
+                Some(existing) => {
+                    existing.depth = existing.depth.min(node.depth);
+                    existing.total_dependents = existing.total_dependents.or(node.total_dependents);
+                },
+                None => self.nodes.push(node),
+            }
+        }
+        self.edges.extend(other.edges);
+        self.root_total += other.root_total;
+        self.crawls += other.crawls;
+        self.truncated |= other.truncated;
+        self
+    }
+}
+
+/// Builds a tree per package and dependent type to crawl and merges them,
+/// sharing `config.crawl_budget` between them.
+pub async fn build_trees(crawler: &Crawler, config: &Config) -> Result<DependencyTree, AppError> {
+    let mut merged: Option<DependencyTree> = None;
+    for package_config in crawler.package_configs(config).await? {
+        for type_config in package_config.type_configs() {
+            let used = merged.as_ref().map_or(0, |tree| tree.crawls);
+            let type_config = Config {
+                crawl_budget: config.crawl_budget.saturating_sub(used),
+                ..type_config
+            };
+            let tree = build_tree(crawler, &type_config).await?;
+            merged = Some(match merged {
+                Some(merged) => merged.merge(tree),
+                None => {
+                    // Every dependents page shows the counts of both types; counts of
+                    // a selected package are not the repository's
+                    if type_config.package_id.is_none() {
+                        record_counts(config, tree.header);
+                    }
+                    tree
+                },
+            });
+        }
+    }
+    merged.ok_or_else(|| AppError::Other("Nothing to crawl".to_string()))
+}
+
+/// Crawls the dependents of the configured repository, then breadth first
+/// the dependents of its top dependents, down to `config.depth` levels. Each
+/// crawled repository contributes its top `config.top_n` dependents; only
+/// those with at least `config.expand_min_stars` stars are crawled in turn,
+/// most starred first, and no more than `config.crawl_budget` lists are
+/// crawled in total. A failing crawl below the root is reported and skipped.
+pub async fn build_tree(crawler: &Crawler, config: &Config) -> Result<DependencyTree, AppError> {
+    let root = format!("{}/{}", config.owner, config.repo).to_lowercase();
+    let mut tree = DependencyTree {
+        root: root.clone(),
+        root_total: 0,
+        nodes: Vec::new(),
+        edges: Vec::new(),
+        crawls: 0,
+        truncated: false,
+        header: HeaderCounts::default(),
+    };
+    let mut seen: HashSet<String> = HashSet::from([root.clone()]);
+    let mut level = vec![(root, config.clone())];
+
+    for depth in 1..=config.depth {
+        let mut next_level = Vec::new();
+        for (repo, repo_config) in level {
+            if tree.crawls >= config.crawl_budget {
+                tree.truncated = true;
+                break;
+            }
+            if depth > 1 && !config.quiet {
+                eprintln!("Fetching dependents of {} (level {})...", repo, depth);
+            }
+
+            tree.crawls += 1;
+            let crawl = match crawler.crawl(&repo_config).await {
+                Ok(crawl) => crawl,
+                Err(e) if depth > 1 => {
+                    eprintln!("Warning: Could not crawl dependents of {}: {}", repo, e);
+                    continue;
+                },
+                Err(e) => return Err(e),
+            };
+            if depth == 1 {
+                if config.save_snapshot {
+                    save_crawl(config, &crawl)?;
+                }
+                tree.root_total = crawl.max_deps;
+                tree.header = crawl.header;
+            } else if let Some(node) = tree.nodes.iter_mut().find(|node| node.dependent.repo == repo) {
+                node.total_dependents = Some(crawl.max_deps);
+            }
+
+            for dep in crawler.top_dependents(&repo_config, &crawl).await {
+                tree.edges.push(TreeEdge {
+                    dependency: repo.clone(),
+                    dependent: dep.repo.clone(),
+                    dependent_type: dep.dependent_type,
+                    package: repo_config.package.clone(),
+                });
+                if !seen.insert(dep.repo.clone()) {
+                    continue;
+                }
+                if depth < config.depth && convert_stars_to_number(&dep.stars) >= config.expand_min_stars {
+                    next_level.push((dep.repo.clone(), dep_config(config, &dep.repo)?));
+                }
+                tree.nodes.push(TreeNode {
+                    dependent: dep,
+                    depth,
+                    total_dependents: None,
+                });
+            }
+        }
+
+        // Spend the remaining budget on the most starred repositories
+        let stars: HashMap<&str, f64> = tree.nodes.iter()
+            .map(|node| (node.dependent.repo.as_str(), convert_stars_to_number(&node.dependent.stars)))
+            .collect();
+        next_level.sort_by(|(a, _), (b, _)| stars[b.as_str()].partial_cmp(&stars[a.as_str()]).unwrap());
+        level = next_level;
+    }
+
+    Ok(tree)
+}
+
+/// The configuration crawling the dependents of a dependent: same type and
+/// filters, no package selection, no snapshots and no status messages or
+/// progress.
+fn dep_config(config: &Config, repo: &str) -> Result<Config, AppError> {
+    Ok(Config {
+        package: None,
+        package_id: None,
+        save_snapshot: false,
+        quiet: true,
+        progress: ProgressMode::Never,
+        ..config.with_target(repo)?
+    })
+}
@@ -0,0 +1,125 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::get_data_dir;
+use crate::config::Config;
+use crate::error::AppError;
+use crate::github::HeaderCounts;
+
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The header counts of one run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrendPoint {
+    /// Seconds since the Unix epoch
+    pub taken_at: u64,
+    pub repositories: Option<usize>,
+    pub packages: Option<usize>,
+}
+
+impl TrendPoint {
+    pub fn new(counts: HeaderCounts, taken_at: SystemTime) -> Self {
+        TrendPoint {
+            taken_at: taken_at.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            repositories: counts.repositories,
+            packages: counts.packages,
+        }
+    }
+}
+
+pub fn default_trend_root() -> PathBuf {
+    get_data_dir().join("trends")
+}
+
+fn trend_path(root: &Path, owner: &str, repo: &str) -> PathBuf {
+    root.join(owner.to_lowercase()).join(format!("{}.jsonl", repo.to_lowercase()))
+}
+
+/// Appends a point to the repository's series, one JSON object per line.
+pub fn append_point(root: &Path, owner: &str, repo: &str, point: &TrendPoint) -> Result<(), AppError> {
+    let path = trend_path(root, owner, repo);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(point)?)?;
+    Ok(())
+}
+
+/// Loads the repository's series, oldest first.
+pub fn load_points(root: &Path, owner: &str, repo: &str) -> Result<Vec<TrendPoint>, AppError> {
+    let path = trend_path(root, owner, repo);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)?;
+    let mut points = content.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| serde_json::from_str::<TrendPoint>(line).map_err(|e| AppError::Other(format!(
+            "{}:{}: {}", path.display(), idx + 1, e
+        ))))
+        .collect::<Result<Vec<_>, _>>()?;
+    points.sort_by_key(|point| point.taken_at);
+    Ok(points)
+}
+
+/// Appends `point` unless it may have been read from the response cache:
+/// when the latest point has the same counts and is younger than
+/// `cache_ttl`. Returns whether the point was appended.
+pub fn record_point(
+    root: &Path,
+    owner: &str,
+    repo: &str,
+    point: &TrendPoint,
+    cache_ttl: Option<Duration>,
+) -> Result<bool, AppError> {
+    if let Some(ttl) = cache_ttl {
+        let cached = load_points(root, owner, repo)?.last().is_some_and(|last| {
+            last.repositories == point.repositories
+                && last.packages == point.packages
+                && point.taken_at.saturating_sub(last.taken_at) < ttl.as_secs()
+        });
+        if cached {
+            return Ok(false);
+        }
+    }
+    append_point(root, owner, repo, point)?;
+    Ok(true)
+}
+
+/// Records the header counts of a run, warning instead of failing when the
+/// store cannot be written.
+pub fn record_counts(config: &Config, counts: HeaderCounts) {
+    if !config.record_trend || counts == HeaderCounts::default() {
+        return;
+    }
+    let point = TrendPoint::new(counts, SystemTime::now());
+    if let Err(e) = record_point(&default_trend_root(), &config.owner, &config.repo, &point, config.cache_ttl()) {
+        eprintln!("Warning: Could not record dependents count: {}", e);
+    }
+}
+
+/// Draws values as a line of block characters scaled between their minimum
+/// and maximum. Missing values are drawn as spaces.
+pub fn sparkline(values: &[Option<usize>]) -> String {
+    let present = values.iter().flatten();
+    let (Some(min), Some(max)) = (present.clone().min(), present.max()) else {
+        return " ".repeat(values.len());
+    };
+
+    values.iter()
+        .map(|value| match value {
+            Some(value) if max > min => {
+                let level = (value - min) * (SPARK_CHARS.len() - 1) / (max - min);
+                SPARK_CHARS[level]
+            },
+            Some(_) => SPARK_CHARS[0],
+            None => ' ',
+        })
+        .collect()
+}
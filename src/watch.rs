@@ -0,0 +1,196 @@
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime};
+use reqwest::Client;
+use serde::Serialize;
+use tokio::time::sleep;
+
+use crate::client::{create_client_with, ClientOptions};
+use crate::config::Config;
+use crate::dependent::convert_stars_to_number;
+use crate::error::AppError;
+use crate::github::Crawler;
+use crate::metrics::serve_metrics;
+use crate::progress::ProgressMode;
+use crate::trend::record_counts;
+use crate::snapshot::{default_snapshot_root, diff_snapshots, format_timestamp, list_snapshots, Snapshot, SnapshotDiff};
+
+pub struct WatchOptions {
+    pub interval: Duration,
+    /// New dependents below this many stars are not reported
+    pub min_stars: f64,
+    /// Star counts reported when a dependent reaches them
+    pub thresholds: Vec<f64>,
+    pub webhook: Option<String>,
+    /// Print events as JSON lines instead of text
+    pub json: bool,
+    /// Address to serve `/metrics` on
+    pub metrics_bind: Option<SocketAddr>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WatchEvent {
+    NewDependent {
+        repo: String,
+        stars: String,
+    },
+    StarThreshold {
+        repo: String,
+        threshold: f64,
+        from: String,
+        to: String,
+    },
+}
+
+/// The body posted to the webhook after every check that found changes.
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload<'a> {
+    pub target: &'a str,
+    pub checked_at: String,
+    pub events: &'a [WatchEvent],
+}
+
+impl WatchEvent {
+    pub fn describe(&self) -> String {
+        match self {
+            WatchEvent::NewDependent { repo, stars } => {
+                format!("new dependent {} (⭐ {})", repo, stars)
+            },
+            WatchEvent::StarThreshold { repo, threshold, from, to } => {
+                format!("{} reached {} stars (⭐ {} -> {})", repo, threshold, from, to)
+            },
+        }
+    }
+}
+
+/// Turns the changes between two crawls into events: new dependents with at
+/// least `min_stars` stars, and dependents whose stars rose to or past one
+/// of the thresholds.
+pub fn watch_events(diff: &SnapshotDiff, options: &WatchOptions) -> Vec<WatchEvent> {
+    let mut events: Vec<WatchEvent> = diff.added.iter()
+        .filter(|dep| convert_stars_to_number(&dep.stars) >= options.min_stars)
+        .map(|dep| WatchEvent::NewDependent {
+            repo: dep.repo.clone(),
+            stars: dep.stars.clone(),
+        })
+        .collect();
+
+    for change in &diff.star_changes {
+        let from = convert_stars_to_number(&change.from);
+        let to = convert_stars_to_number(&change.to);
+        events.extend(options.thresholds.iter()
+            .filter(|&&threshold| from < threshold && to >= threshold)
+            .map(|&threshold| WatchEvent::StarThreshold {
+                repo: change.repo.clone(),
+                threshold,
+                from: change.from.clone(),
+                to: change.to.clone(),
+            }));
+    }
+
+    events
+}
+
+/// A client for posting to webhooks: the network options of `options`,
+/// without the GitHub token, which must not reach third-party hosts.
+pub fn webhook_client(options: &ClientOptions) -> Result<Client, AppError> {
+    let mut options = options.clone();
+    options.token = None;
+    create_client_with(&options)
+}
+
+pub async fn send_webhook(client: &Client, url: &str, payload: &WebhookPayload<'_>) -> Result<(), AppError> {
+    let response = client.post(url).json(payload).send().await?;
+    if !response.status().is_success() {
+        return Err(AppError::Other(format!("Webhook returned {}", response.status())));
+    }
+    Ok(())
+}
+
+fn print_events(payload: &WebhookPayload<'_>, json: bool) -> Result<(), AppError> {
+    for event in payload.events {
+        if json {
+            let line = serde_json::json!({
+                "target": payload.target,
+                "checked_at": payload.checked_at,
+                "event": event,
+            });
+            println!("{}", serde_json::to_string(&line)?);
+        } else {
+            println!("{} {}: {}", payload.checked_at, payload.target, event.describe());
+        }
+    }
+    Ok(())
+}
+
+/// Re-crawls the target every `interval` and reports what changed since the
+/// previous crawl. The latest stored snapshot, if any, is the first baseline;
+/// otherwise the first crawl is. Runs until interrupted.
+pub async fn run_watch(config: &Config, options: &WatchOptions) -> Result<(), AppError> {
+    let client_options = config.client_options()?;
+    let crawler = Crawler::new(&client_options)?;
+    let webhook_client = webhook_client(&client_options)?;
+    let target = format!("{}/{}", config.owner, config.repo);
+    let config = &crawler.package_configs(config).await?[0];
+
+    let mut previous = match list_snapshots(&default_snapshot_root(), config)?.last() {
+        Some(path) => Some(Snapshot::load(path)?),
+        None => None,
+    };
+    // Events are the only output; only the first crawl may be served from the cache
+    let mut crawl_config = Config {
+        quiet: true,
+        progress: ProgressMode::Never,
+        ..config.clone()
+    };
+    if !config.quiet {
+        eprintln!("Watching {} every {}", target, humantime::format_duration(options.interval));
+    }
+    if let Some(bind) = options.metrics_bind {
+        tokio::spawn(async move {
+            if let Err(e) = serve_metrics(bind).await {
+                eprintln!("Warning: {}", e);
+            }
+        });
+    }
+
+    loop {
+        match crawler.crawl(&crawl_config).await {
+            Ok(crawl) => {
+                if config.package_id.is_none() {
+                    record_counts(config, crawl.header);
+                }
+                let now = SystemTime::now();
+                let current = Snapshot::new(config, &crawl, now);
+                // A failed save must not end the watch
+                if config.save_snapshot {
+                    if let Err(e) = current.save(&default_snapshot_root()) {
+                        eprintln!("Warning: Could not save snapshot: {}", e);
+                    }
+                }
+
+                if let Some(previous) = &previous {
+                    let events = watch_events(&diff_snapshots(previous, &current), options);
+                    if !events.is_empty() {
+                        let payload = WebhookPayload {
+                            target: &target,
+                            checked_at: format_timestamp(current.taken_at),
+                            events: &events,
+                        };
+                        print_events(&payload, options.json)?;
+                        if let Some(url) = &options.webhook {
+                            if let Err(e) = send_webhook(&webhook_client, url, &payload).await {
+                                eprintln!("Warning: Could not send webhook: {}", e);
+                            }
+                        }
+                    }
+                }
+                previous = Some(current);
+            },
+            Err(e) => eprintln!("Error crawling {}: {}", target, e),
+        }
+
+        crawl_config.use_cache = false;
+        sleep(options.interval).await;
+    }
+}